@@ -1,3 +1,39 @@
+use std::path::Path;
+use std::{env, fs};
+
 fn main() {
+    embed_migrations();
     tauri_build::build()
 }
+
+/// Generate `$OUT_DIR/migrations.rs` embedding every `.sql` file in
+/// `migrations/`, so adding a migration only means adding a file.
+fn embed_migrations() {
+    println!("cargo:rerun-if-changed=migrations");
+
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR not set");
+    let migrations_dir = Path::new(&manifest_dir).join("migrations");
+
+    let mut files: Vec<_> = fs::read_dir(&migrations_dir)
+        .expect("Failed to read migrations directory")
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "sql"))
+        .collect();
+    files.sort();
+
+    let mut out = String::from("pub(crate) static EMBEDDED_MIGRATIONS: &[(&str, &str)] = &[\n");
+    for path in &files {
+        println!("cargo:rerun-if-changed={}", path.display());
+        let name = path.file_name().unwrap().to_string_lossy();
+        out += &format!(
+            "    ({:?}, include_str!({:?})),\n",
+            name,
+            path.display().to_string()
+        );
+    }
+    out += "];\n";
+
+    let out_dir = env::var("OUT_DIR").expect("OUT_DIR not set");
+    fs::write(Path::new(&out_dir).join("migrations.rs"), out)
+        .expect("Failed to write embedded migrations");
+}
@@ -0,0 +1,9 @@
+-- Revert version 1: drop the initial schema
+
+DROP TRIGGER IF EXISTS update_task_timestamp;
+DROP TRIGGER IF EXISTS enforce_one_now_task;
+
+DROP TABLE IF EXISTS sync_log;
+DROP TABLE IF EXISTS review_log;
+DROP TABLE IF EXISTS settings;
+DROP TABLE IF EXISTS tasks;
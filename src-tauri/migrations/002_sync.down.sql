@@ -0,0 +1,14 @@
+-- Revert version 2: git sync support
+
+DROP TRIGGER IF EXISTS update_task_timestamp;
+
+CREATE TRIGGER IF NOT EXISTS update_task_timestamp
+AFTER UPDATE ON tasks
+FOR EACH ROW
+BEGIN
+    UPDATE tasks SET updated_at = strftime('%s', 'now') WHERE id = NEW.id;
+END;
+
+DROP INDEX IF EXISTS idx_tasks_updated;
+
+DELETE FROM settings WHERE key = 'git_sync_remote';
@@ -0,0 +1,21 @@
+-- TaskFlow Database Schema
+-- Version: 2
+-- Git sync support
+
+-- Only stamp updated_at when the caller didn't set it explicitly, so tasks
+-- imported by the sync engine keep the timestamp they were written with.
+DROP TRIGGER IF EXISTS update_task_timestamp;
+
+CREATE TRIGGER IF NOT EXISTS update_task_timestamp
+AFTER UPDATE ON tasks
+FOR EACH ROW
+WHEN NEW.updated_at = OLD.updated_at
+BEGIN
+    UPDATE tasks SET updated_at = strftime('%s', 'now') WHERE id = NEW.id;
+END;
+
+CREATE INDEX IF NOT EXISTS idx_tasks_updated ON tasks(updated_at);
+
+-- Remote the sync engine pushes to and pulls from (empty = commit locally only)
+INSERT OR IGNORE INTO settings (key, value, updated_at) VALUES
+    ('git_sync_remote', '', strftime('%s', 'now'));
@@ -0,0 +1,13 @@
+-- Revert version 3: fold normalized tags back into a comma-separated column
+
+ALTER TABLE tasks ADD COLUMN tags TEXT;
+
+UPDATE tasks SET tags = (
+    SELECT group_concat(tags.name, ',')
+    FROM task_tags JOIN tags ON tags.id = task_tags.tag_id
+    WHERE task_tags.task_id = tasks.id
+);
+
+DROP INDEX IF EXISTS idx_task_tags_tag;
+DROP TABLE IF EXISTS task_tags;
+DROP TABLE IF EXISTS tags;
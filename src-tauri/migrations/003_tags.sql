@@ -0,0 +1,44 @@
+-- TaskFlow Database Schema
+-- Version: 3
+-- Normalized tags replacing the free-text tasks.tags column
+
+CREATE TABLE IF NOT EXISTS tags (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    created_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS task_tags (
+    task_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+    tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    PRIMARY KEY (task_id, tag_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_task_tags_tag ON task_tags(tag_id);
+
+-- Carry over legacy free-text tags, treating commas, spaces and '#' as separators
+WITH RECURSIVE split(task_id, rest, tag) AS (
+    SELECT id, REPLACE(REPLACE(tags, '#', ','), ' ', ',') || ',', ''
+    FROM tasks WHERE tags IS NOT NULL AND TRIM(tags) != ''
+    UNION ALL
+    SELECT task_id,
+           SUBSTR(rest, INSTR(rest, ',') + 1),
+           LOWER(TRIM(SUBSTR(rest, 1, INSTR(rest, ',') - 1)))
+    FROM split WHERE rest != ''
+)
+INSERT OR IGNORE INTO tags (name, created_at)
+SELECT DISTINCT tag, strftime('%s', 'now') FROM split WHERE tag != '';
+
+WITH RECURSIVE split(task_id, rest, tag) AS (
+    SELECT id, REPLACE(REPLACE(tags, '#', ','), ' ', ',') || ',', ''
+    FROM tasks WHERE tags IS NOT NULL AND TRIM(tags) != ''
+    UNION ALL
+    SELECT task_id,
+           SUBSTR(rest, INSTR(rest, ',') + 1),
+           LOWER(TRIM(SUBSTR(rest, 1, INSTR(rest, ',') - 1)))
+    FROM split WHERE rest != ''
+)
+INSERT OR IGNORE INTO task_tags (task_id, tag_id)
+SELECT split.task_id, tags.id FROM split JOIN tags ON tags.name = split.tag;
+
+ALTER TABLE tasks DROP COLUMN tags;
@@ -0,0 +1,7 @@
+-- Revert version 4: full-text search
+
+DROP TRIGGER IF EXISTS tasks_fts_delete;
+DROP TRIGGER IF EXISTS tasks_fts_update;
+DROP TRIGGER IF EXISTS tasks_fts_insert;
+
+DROP TABLE IF EXISTS tasks_fts;
@@ -0,0 +1,37 @@
+-- TaskFlow Database Schema
+-- Version: 4
+-- Full-text search over task titles and original input
+
+-- Standalone FTS table keyed by task id rather than an external-content
+-- table on tasks.rowid, since tasks has no INTEGER PRIMARY KEY and VACUUM
+-- may renumber its rowids.
+CREATE VIRTUAL TABLE IF NOT EXISTS tasks_fts USING fts5(
+    task_id UNINDEXED,
+    title,
+    original_input,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+INSERT INTO tasks_fts (task_id, title, original_input)
+SELECT id, title, COALESCE(original_input, '') FROM tasks;
+
+CREATE TRIGGER IF NOT EXISTS tasks_fts_insert
+AFTER INSERT ON tasks
+BEGIN
+    INSERT INTO tasks_fts (task_id, title, original_input)
+    VALUES (NEW.id, NEW.title, COALESCE(NEW.original_input, ''));
+END;
+
+CREATE TRIGGER IF NOT EXISTS tasks_fts_update
+AFTER UPDATE OF title, original_input ON tasks
+BEGIN
+    DELETE FROM tasks_fts WHERE task_id = OLD.id;
+    INSERT INTO tasks_fts (task_id, title, original_input)
+    VALUES (NEW.id, NEW.title, COALESCE(NEW.original_input, ''));
+END;
+
+CREATE TRIGGER IF NOT EXISTS tasks_fts_delete
+AFTER DELETE ON tasks
+BEGIN
+    DELETE FROM tasks_fts WHERE task_id = OLD.id;
+END;
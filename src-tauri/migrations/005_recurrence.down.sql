@@ -0,0 +1,3 @@
+-- Revert version 5: recurring tasks
+
+ALTER TABLE tasks DROP COLUMN recurrence;
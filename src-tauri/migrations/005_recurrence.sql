@@ -0,0 +1,5 @@
+-- TaskFlow Database Schema
+-- Version: 5
+-- Recurring tasks: RRULE-style repeat rule per task
+
+ALTER TABLE tasks ADD COLUMN recurrence TEXT;
@@ -0,0 +1,3 @@
+-- Revert version 6: user timezone
+
+DELETE FROM settings WHERE key = 'timezone';
@@ -0,0 +1,6 @@
+-- TaskFlow Database Schema
+-- Version: 6
+-- User timezone for resolving dates in quick capture (empty = system timezone)
+
+INSERT OR IGNORE INTO settings (key, value, updated_at) VALUES
+    ('timezone', '', strftime('%s', 'now'));
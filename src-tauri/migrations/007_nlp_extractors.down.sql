@@ -0,0 +1,3 @@
+-- Revert version 7: quick-capture extractor settings
+
+DELETE FROM settings WHERE key = 'nlp_extractors';
@@ -0,0 +1,7 @@
+-- TaskFlow Database Schema
+-- Version: 7
+-- Quick-capture extractors to run, in order, as a JSON array of names
+-- (empty = every built-in extractor in its default order)
+
+INSERT OR IGNORE INTO settings (key, value, updated_at) VALUES
+    ('nlp_extractors', '', strftime('%s', 'now'));
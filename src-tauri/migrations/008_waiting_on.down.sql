@@ -0,0 +1,3 @@
+-- Revert version 8: waiting_on
+
+ALTER TABLE tasks DROP COLUMN waiting_on;
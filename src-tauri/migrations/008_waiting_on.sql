@@ -0,0 +1,5 @@
+-- TaskFlow Database Schema
+-- Version: 8
+-- Who a WAITING task is waiting on
+
+ALTER TABLE tasks ADD COLUMN waiting_on TEXT;
@@ -0,0 +1,7 @@
+-- Revert version 9: waiting-for tracking
+
+DELETE FROM settings WHERE key = 'follow_up_interval_days';
+DROP INDEX IF EXISTS idx_follow_ups_task;
+DROP TABLE IF EXISTS follow_ups;
+ALTER TABLE tasks DROP COLUMN follow_up_at;
+ALTER TABLE tasks DROP COLUMN waiting_since;
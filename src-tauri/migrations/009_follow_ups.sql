@@ -0,0 +1,22 @@
+-- TaskFlow Database Schema
+-- Version: 9
+-- Waiting-for tracking: when a task started waiting, when to chase, and a
+-- log of follow-up nudges
+
+ALTER TABLE tasks ADD COLUMN waiting_since INTEGER;
+ALTER TABLE tasks ADD COLUMN follow_up_at INTEGER;
+
+-- Tasks already waiting have been waiting since at least their last edit
+UPDATE tasks SET waiting_since = updated_at WHERE status = 'waiting';
+
+CREATE TABLE IF NOT EXISTS follow_ups (
+    id TEXT PRIMARY KEY NOT NULL,
+    task_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+    nudged_at INTEGER NOT NULL,
+    note TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_follow_ups_task ON follow_ups(task_id, nudged_at);
+
+INSERT OR IGNORE INTO settings (key, value, updated_at) VALUES
+    ('follow_up_interval_days', '3', strftime('%s', 'now'));
@@ -0,0 +1,6 @@
+-- Revert version 10: priority and effort
+
+DROP INDEX IF EXISTS idx_tasks_priority;
+ALTER TABLE tasks DROP COLUMN energy;
+ALTER TABLE tasks DROP COLUMN effort_minutes;
+ALTER TABLE tasks DROP COLUMN priority;
@@ -0,0 +1,10 @@
+-- TaskFlow Database Schema
+-- Version: 10
+-- Priority (1 = high, 2 = medium, 3 = low), estimated effort in minutes and
+-- energy level ('low', 'medium', 'high')
+
+ALTER TABLE tasks ADD COLUMN priority INTEGER CHECK (priority BETWEEN 1 AND 3);
+ALTER TABLE tasks ADD COLUMN effort_minutes INTEGER CHECK (effort_minutes > 0);
+ALTER TABLE tasks ADD COLUMN energy TEXT CHECK (energy IN ('low', 'medium', 'high'));
+
+CREATE INDEX IF NOT EXISTS idx_tasks_priority ON tasks(status, priority);
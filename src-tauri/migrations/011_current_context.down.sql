@@ -0,0 +1,3 @@
+-- Revert version 11: current context
+
+DELETE FROM settings WHERE key = 'current_context';
@@ -0,0 +1,7 @@
+-- TaskFlow Database Schema
+-- Version: 11
+-- Context the user is working in; the dashboard, suggestions and opted-in
+-- listings show only tasks for it (empty = all contexts)
+
+INSERT OR IGNORE INTO settings (key, value, updated_at) VALUES
+    ('current_context', '', strftime('%s', 'now'));
@@ -0,0 +1,8 @@
+-- Revert version 12: projects and subtasks
+
+DROP INDEX IF EXISTS idx_tasks_parent;
+DROP INDEX IF EXISTS idx_tasks_project;
+ALTER TABLE tasks DROP COLUMN parent_id;
+ALTER TABLE tasks DROP COLUMN project_id;
+DROP INDEX IF EXISTS idx_projects_status;
+DROP TABLE IF EXISTS projects;
@@ -0,0 +1,6 @@
+-- Revert version 13: soft delete
+
+DELETE FROM settings WHERE key = 'trash_retention_days';
+DELETE FROM tasks WHERE deleted_at IS NOT NULL;
+DROP INDEX IF EXISTS idx_tasks_deleted;
+ALTER TABLE tasks DROP COLUMN deleted_at;
@@ -0,0 +1,11 @@
+-- TaskFlow Database Schema
+-- Version: 13
+-- Soft delete: deleted tasks keep a tombstone until purged
+
+ALTER TABLE tasks ADD COLUMN deleted_at INTEGER;
+
+CREATE INDEX IF NOT EXISTS idx_tasks_deleted ON tasks(deleted_at) WHERE deleted_at IS NOT NULL;
+
+-- Days a task stays in the trash before it is purged for good
+INSERT OR IGNORE INTO settings (key, value, updated_at) VALUES
+    ('trash_retention_days', '30', strftime('%s', 'now'));
@@ -0,0 +1,4 @@
+-- Revert version 14: undo journal
+
+DROP INDEX IF EXISTS idx_undo_journal_undone;
+DROP TABLE IF EXISTS undo_journal;
@@ -0,0 +1,16 @@
+-- TaskFlow Database Schema
+-- Version: 14
+-- Undo/redo journal of task mutations
+
+CREATE TABLE IF NOT EXISTS undo_journal (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    operation TEXT NOT NULL CHECK(operation IN ('create_task', 'update_task_status', 'delete_task')),
+    task_id TEXT,
+    -- JSON list of {id, before, after} task snapshots; null = task absent
+    changes TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    -- Undone entries form the redo stack
+    undone INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE INDEX IF NOT EXISTS idx_undo_journal_undone ON undo_journal(undone, id);
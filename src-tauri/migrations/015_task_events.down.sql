@@ -0,0 +1,9 @@
+-- Revert version 15: task change history
+
+DROP TRIGGER IF EXISTS task_events_tag_removed;
+DROP TRIGGER IF EXISTS task_events_tag_added;
+DROP TRIGGER IF EXISTS task_events_update;
+DROP TRIGGER IF EXISTS task_events_insert;
+DROP TABLE IF EXISTS task_event_source;
+DROP INDEX IF EXISTS idx_task_events_task;
+DROP TABLE IF EXISTS task_events;
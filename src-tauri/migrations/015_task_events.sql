@@ -0,0 +1,76 @@
+-- TaskFlow Database Schema
+-- Version: 15
+-- Change history: one row per field change, recorded by triggers
+
+CREATE TABLE IF NOT EXISTS task_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    task_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+    -- 'created', a task column name, or 'tags'
+    field TEXT NOT NULL,
+    old_value TEXT,
+    new_value TEXT,
+    created_at INTEGER NOT NULL,
+    source TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_task_events_task ON task_events(task_id, id);
+
+-- What is changing tasks right now ('app', 'sync', 'undo', ...). The query
+-- layer switches it around work done on behalf of something other than the
+-- user; the triggers stamp it on every event.
+CREATE TABLE IF NOT EXISTS task_event_source (
+    id INTEGER PRIMARY KEY CHECK(id = 1),
+    source TEXT NOT NULL
+);
+
+INSERT OR IGNORE INTO task_event_source (id, source) VALUES (1, 'app');
+
+CREATE TRIGGER IF NOT EXISTS task_events_insert
+AFTER INSERT ON tasks
+BEGIN
+    INSERT INTO task_events (task_id, field, old_value, new_value, created_at, source)
+    VALUES (NEW.id, 'created', NULL, NEW.status, strftime('%s', 'now'),
+            (SELECT source FROM task_event_source WHERE id = 1));
+END;
+
+CREATE TRIGGER IF NOT EXISTS task_events_update
+AFTER UPDATE ON tasks
+BEGIN
+    INSERT INTO task_events (task_id, field, old_value, new_value, created_at, source)
+    SELECT NEW.id, changes.field, changes.old_value, changes.new_value, strftime('%s', 'now'),
+           (SELECT source FROM task_event_source WHERE id = 1)
+    FROM (
+        SELECT 'title' AS field, OLD.title AS old_value, NEW.title AS new_value
+        UNION ALL SELECT 'status', OLD.status, NEW.status
+        UNION ALL SELECT 'context', OLD.context, NEW.context
+        UNION ALL SELECT 'scheduled_for', OLD.scheduled_for, NEW.scheduled_for
+        UNION ALL SELECT 'recurrence', OLD.recurrence, NEW.recurrence
+        UNION ALL SELECT 'waiting_on', OLD.waiting_on, NEW.waiting_on
+        UNION ALL SELECT 'follow_up_at', OLD.follow_up_at, NEW.follow_up_at
+        UNION ALL SELECT 'priority', OLD.priority, NEW.priority
+        UNION ALL SELECT 'effort_minutes', OLD.effort_minutes, NEW.effort_minutes
+        UNION ALL SELECT 'energy', OLD.energy, NEW.energy
+        UNION ALL SELECT 'project_id', OLD.project_id, NEW.project_id
+        UNION ALL SELECT 'parent_id', OLD.parent_id, NEW.parent_id
+        UNION ALL SELECT 'deleted_at', OLD.deleted_at, NEW.deleted_at
+    ) AS changes
+    WHERE changes.old_value IS NOT changes.new_value;
+END;
+
+CREATE TRIGGER IF NOT EXISTS task_events_tag_added
+AFTER INSERT ON task_tags
+BEGIN
+    INSERT INTO task_events (task_id, field, old_value, new_value, created_at, source)
+    VALUES (NEW.task_id, 'tags', NULL, (SELECT name FROM tags WHERE id = NEW.tag_id),
+            strftime('%s', 'now'), (SELECT source FROM task_event_source WHERE id = 1));
+END;
+
+-- Skip links removed because the task itself is being purged
+CREATE TRIGGER IF NOT EXISTS task_events_tag_removed
+AFTER DELETE ON task_tags
+WHEN EXISTS (SELECT 1 FROM tasks WHERE id = OLD.task_id)
+BEGIN
+    INSERT INTO task_events (task_id, field, old_value, new_value, created_at, source)
+    VALUES (OLD.task_id, 'tags', (SELECT name FROM tags WHERE id = OLD.tag_id), NULL,
+            strftime('%s', 'now'), (SELECT source FROM task_event_source WHERE id = 1));
+END;
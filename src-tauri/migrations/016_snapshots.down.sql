@@ -0,0 +1,3 @@
+-- Revert version 16: snapshot retention
+
+DELETE FROM settings WHERE key IN ('snapshot_keep_daily', 'snapshot_keep_weekly');
@@ -0,0 +1,7 @@
+-- TaskFlow Database Schema
+-- Version: 16
+-- Rolling on-disk snapshots: how many daily and weekly copies to keep
+
+INSERT OR IGNORE INTO settings (key, value, updated_at) VALUES
+    ('snapshot_keep_daily', '7', strftime('%s', 'now')),
+    ('snapshot_keep_weekly', '4', strftime('%s', 'now'));
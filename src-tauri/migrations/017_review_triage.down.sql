@@ -0,0 +1,3 @@
+-- Revert version 17: review triage tracking
+
+DROP TABLE IF EXISTS review_triage;
@@ -0,0 +1,10 @@
+-- TaskFlow Database Schema
+-- Version: 17
+-- Tasks already triaged in each review, so a decision is only counted once
+
+CREATE TABLE IF NOT EXISTS review_triage (
+    review_id TEXT NOT NULL REFERENCES review_log(id) ON DELETE CASCADE,
+    task_id TEXT NOT NULL,
+    triaged_at INTEGER NOT NULL,
+    PRIMARY KEY (review_id, task_id)
+);
@@ -0,0 +1,63 @@
+-- TaskFlow Database Schema
+-- Version: 18
+-- Deleting a project no longer deletes its tasks
+
+-- Tasks outlive their project row. delete_project moves them to the trash
+-- first, where they can still be restored; a project removed by sync leaves
+-- its tasks in place without a project.
+
+-- SQLite can't change a foreign key in place, so the column is dropped and
+-- added back. The triggers that would stamp or log the copy are set aside
+-- meanwhile.
+CREATE TEMP TABLE task_projects AS
+    SELECT id, project_id FROM tasks WHERE project_id IS NOT NULL;
+
+DROP TRIGGER IF EXISTS update_task_timestamp;
+DROP TRIGGER IF EXISTS task_events_update;
+DROP INDEX IF EXISTS idx_tasks_project;
+
+ALTER TABLE tasks DROP COLUMN project_id;
+ALTER TABLE tasks ADD COLUMN project_id TEXT REFERENCES projects(id) ON DELETE SET NULL;
+
+UPDATE tasks
+SET project_id = (SELECT project_id FROM task_projects WHERE task_projects.id = tasks.id)
+WHERE id IN (
+    SELECT task_projects.id FROM task_projects
+    JOIN projects ON projects.id = task_projects.project_id
+);
+
+DROP TABLE task_projects;
+
+CREATE INDEX IF NOT EXISTS idx_tasks_project ON tasks(project_id);
+
+CREATE TRIGGER IF NOT EXISTS update_task_timestamp
+AFTER UPDATE ON tasks
+FOR EACH ROW
+WHEN NEW.updated_at = OLD.updated_at
+BEGIN
+    UPDATE tasks SET updated_at = strftime('%s', 'now') WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS task_events_update
+AFTER UPDATE ON tasks
+BEGIN
+    INSERT INTO task_events (task_id, field, old_value, new_value, created_at, source)
+    SELECT NEW.id, changes.field, changes.old_value, changes.new_value, strftime('%s', 'now'),
+           (SELECT source FROM task_event_source WHERE id = 1)
+    FROM (
+        SELECT 'title' AS field, OLD.title AS old_value, NEW.title AS new_value
+        UNION ALL SELECT 'status', OLD.status, NEW.status
+        UNION ALL SELECT 'context', OLD.context, NEW.context
+        UNION ALL SELECT 'scheduled_for', OLD.scheduled_for, NEW.scheduled_for
+        UNION ALL SELECT 'recurrence', OLD.recurrence, NEW.recurrence
+        UNION ALL SELECT 'waiting_on', OLD.waiting_on, NEW.waiting_on
+        UNION ALL SELECT 'follow_up_at', OLD.follow_up_at, NEW.follow_up_at
+        UNION ALL SELECT 'priority', OLD.priority, NEW.priority
+        UNION ALL SELECT 'effort_minutes', OLD.effort_minutes, NEW.effort_minutes
+        UNION ALL SELECT 'energy', OLD.energy, NEW.energy
+        UNION ALL SELECT 'project_id', OLD.project_id, NEW.project_id
+        UNION ALL SELECT 'parent_id', OLD.parent_id, NEW.parent_id
+        UNION ALL SELECT 'deleted_at', OLD.deleted_at, NEW.deleted_at
+    ) AS changes
+    WHERE changes.old_value IS NOT changes.new_value;
+END;
@@ -1,33 +1,90 @@
 use crate::{
-    db::{DashboardData, Task, TaskStatus},
-    nlp, AppState,
+    db::{
+        Backup, ContextCount, DashboardData, Database, FollowUp, ImportReport, JournalEntry,
+        JournalOperation, NewTask, Progress, Project, ProjectPatch, ProjectStatus, ProjectSummary,
+        RestoreMode, RestoreSummary, ReviewDecision, ReviewLog, SearchResult, Stats, Suggestion,
+        SyncLog, TagCount, Task, TaskEvent, TaskPage, TaskPatch, TaskQuery, TaskStatus, UndoState,
+    },
+    nlp,
+    snapshots::Snapshot,
+    sync::{self, SyncSummary},
+    AppState,
 };
+use chrono::Utc;
 use tauri::State;
 
 #[tauri::command]
 pub fn quick_capture(title: String, state: State<AppState>) -> Result<Task, String> {
     let db = state.db.lock().map_err(|e| e.to_string())?;
 
-    let parsed = nlp::parse_task_input(&title);
+    let parsed = parse_capture(&state.nlp, &db, &title)?;
 
-    db.create_task(
-        parsed.title,
-        TaskStatus::Next,
-        parsed.context,
-        parsed.scheduled_for,
-        Some(title),
-        "quick_capture".to_string(),
-        None,
-    )
+    db.journaled(JournalOperation::CreateTask, None, |db| {
+        db.insert_task(&NewTask {
+            title: parsed.title,
+            status: parsed.status.unwrap_or(TaskStatus::Next),
+            context: parsed.context,
+            scheduled_for: parsed.scheduled_for,
+            original_input: Some(title),
+            source: "quick_capture".to_string(),
+            tags: parsed.tags,
+            recurrence: parsed.recurrence,
+            waiting_on: parsed.waiting_on,
+            priority: parsed.priority,
+            effort_minutes: parsed.effort_minutes,
+            energy: parsed.energy,
+            project_id: None,
+            parent_id: None,
+        })
+    })
     .map_err(|e| e.to_string())
 }
 
+/// Parse capture input without creating a task, for live highlighting
+#[tauri::command]
+pub fn preview_capture(title: String, state: State<AppState>) -> Result<nlp::ParsedTask, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    parse_capture(&state.nlp, &db, &title)
+}
+
+/// Parse capture input in the user's timezone with the configured extractors
+fn parse_capture(
+    pipeline: &nlp::Pipeline,
+    db: &Database,
+    input: &str,
+) -> Result<nlp::ParsedTask, String> {
+    let tz = db.timezone().map_err(|e| e.to_string())?;
+    let now = Utc::now().with_timezone(&tz);
+
+    match db.nlp_extractors().map_err(|e| e.to_string())? {
+        Some(enabled) => Ok(pipeline.parse_with(input, now, &enabled)),
+        None => Ok(pipeline.parse(input, now)),
+    }
+}
+
 #[tauri::command]
 pub fn get_dashboard_data(state: State<AppState>) -> Result<DashboardData, String> {
     let db = state.db.lock().map_err(|e| e.to_string())?;
     db.get_dashboard_data().map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub fn list_tasks(query: Option<TaskQuery>, state: State<AppState>) -> Result<TaskPage, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.list_tasks(&query.unwrap_or_default())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn search_tasks(
+    query: String,
+    limit: Option<u32>,
+    state: State<AppState>,
+) -> Result<Vec<SearchResult>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.search_tasks(&query, limit).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn update_task_status(
     id: String,
@@ -39,12 +96,439 @@ pub fn update_task_status(
     let task_status =
         TaskStatus::from_str(&status).ok_or_else(|| format!("Invalid status: {}", status))?;
 
-    db.update_task_status(&id, task_status)
-        .map_err(|e| e.to_string())
+    db.journaled(JournalOperation::UpdateTaskStatus, Some(&id), |db| {
+        db.update_task_status(&id, task_status)
+    })
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn update_task(id: String, patch: TaskPatch, state: State<AppState>) -> Result<Task, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.update_task(&id, patch).map_err(|e| e.to_string())
 }
 
+/// Move a task and its subtasks to the trash
 #[tauri::command]
 pub fn delete_task(id: String, state: State<AppState>) -> Result<(), String> {
     let db = state.db.lock().map_err(|e| e.to_string())?;
-    db.delete_task(&id).map_err(|e| e.to_string())
+    db.journaled(JournalOperation::DeleteTask, Some(&id), |db| {
+        db.delete_task(&id)
+    })
+    .map_err(|e| e.to_string())
+}
+
+/// Revert the last task creation, status change or deletion
+#[tauri::command]
+pub fn undo(state: State<AppState>) -> Result<Option<JournalEntry>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.undo().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn redo(state: State<AppState>) -> Result<Option<JournalEntry>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.redo().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_undo_state(state: State<AppState>) -> Result<UndoState, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.undo_state().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_task_history(id: String, state: State<AppState>) -> Result<Vec<TaskEvent>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.get_task_history(&id).map_err(|e| e.to_string())
+}
+
+/// Stats for `from..to`, defaulting to the last 30 days
+#[tauri::command]
+pub fn get_stats(
+    from: Option<i64>,
+    to: Option<i64>,
+    state: State<AppState>,
+) -> Result<Stats, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let to = to.unwrap_or_else(|| Utc::now().timestamp());
+    let from = from.unwrap_or(to - 30 * 86400);
+    db.get_stats(from, to).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn export_todotxt(state: State<AppState>) -> Result<String, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.export_todotxt().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn export_markdown(state: State<AppState>) -> Result<String, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.export_markdown().map_err(|e| e.to_string())
+}
+
+/// Import todo.txt text; a dry run only reports what would be created
+#[tauri::command]
+pub fn import_todotxt(
+    text: String,
+    dry_run: bool,
+    state: State<AppState>,
+) -> Result<ImportReport, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.import_todotxt(&text, dry_run).map_err(|e| e.to_string())
+}
+
+/// The whole database as a JSON backup
+#[tauri::command]
+pub fn export_backup(state: State<AppState>) -> Result<String, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let backup = db.export_backup().map_err(|e| e.to_string())?;
+    serde_json::to_string_pretty(&backup).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn restore_backup(
+    json: String,
+    mode: RestoreMode,
+    state: State<AppState>,
+) -> Result<RestoreSummary, String> {
+    let backup: Backup =
+        serde_json::from_str(&json).map_err(|e| format!("Invalid backup file: {}", e))?;
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.restore_backup(&backup, mode).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn create_snapshot(state: State<AppState>) -> Result<Snapshot, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    state.snapshots.create(&db).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn list_snapshots(state: State<AppState>) -> Result<Vec<Snapshot>, String> {
+    state.snapshots.list().map_err(|e| e.to_string())
+}
+
+/// Replace the database with a snapshot, snapshotting the current state first
+#[tauri::command]
+pub fn restore_snapshot(name: String, state: State<AppState>) -> Result<Snapshot, String> {
+    let mut db = state.db.lock().map_err(|e| e.to_string())?;
+    state
+        .snapshots
+        .restore(&mut db, &name)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn configure_snapshots(
+    keep_daily: u32,
+    keep_weekly: u32,
+    state: State<AppState>,
+) -> Result<(), String> {
+    if keep_daily == 0 || keep_weekly == 0 {
+        return Err("Keep at least one daily and one weekly snapshot".to_string());
+    }
+
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+
+    db.set_setting("snapshot_keep_daily", &keep_daily.to_string())
+        .and_then(|_| db.set_setting("snapshot_keep_weekly", &keep_weekly.to_string()))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_trash(state: State<AppState>) -> Result<Vec<Task>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.get_trash().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn restore_task(id: String, state: State<AppState>) -> Result<Task, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.restore_task(&id).map_err(|e| e.to_string())
+}
+
+/// Permanently delete a task that is in the trash
+#[tauri::command]
+pub fn purge_task(id: String, state: State<AppState>) -> Result<(), String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.purge_task(&id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn empty_trash(state: State<AppState>) -> Result<usize, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.empty_trash().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_trash_retention_days(state: State<AppState>) -> Result<i64, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.trash_retention_days().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn set_trash_retention_days(days: i64, state: State<AppState>) -> Result<(), String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.set_trash_retention_days(days).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn add_task_tag(id: String, tag: String, state: State<AppState>) -> Result<Task, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.add_task_tag(&id, &tag).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn remove_task_tag(id: String, tag: String, state: State<AppState>) -> Result<Task, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.remove_task_tag(&id, &tag).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn list_tags(state: State<AppState>) -> Result<Vec<TagCount>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.list_tags().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn list_contexts(state: State<AppState>) -> Result<Vec<ContextCount>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.list_contexts().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_current_context(state: State<AppState>) -> Result<Option<String>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.current_context().map_err(|e| e.to_string())
+}
+
+/// Enter context mode, or leave it with `null`. Returns the stored name.
+#[tauri::command]
+pub fn set_current_context(
+    context: Option<String>,
+    state: State<AppState>,
+) -> Result<Option<String>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.set_current_context(context.as_deref())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_tasks_by_tag(tag: String, state: State<AppState>) -> Result<Vec<Task>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.get_tasks_by_tag(&tag).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn start_review(state: State<AppState>) -> Result<ReviewLog, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.start_review().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_review_tasks(state: State<AppState>) -> Result<Vec<Task>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.get_review_tasks().map_err(|e| e.to_string())
+}
+
+/// Active projects for the review, those missing a NEXT action first
+#[tauri::command]
+pub fn get_review_projects(state: State<AppState>) -> Result<Vec<ProjectSummary>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.get_review_projects().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn record_review_decision(
+    review_id: String,
+    task_id: String,
+    decision: String,
+    state: State<AppState>,
+) -> Result<ReviewLog, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+
+    let review_decision = ReviewDecision::from_str(&decision)
+        .ok_or_else(|| format!("Invalid review decision: {}", decision))?;
+
+    db.record_review_decision(&review_id, &task_id, review_decision)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn finish_review(review_id: String, state: State<AppState>) -> Result<ReviewLog, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.finish_review(&review_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn sync_now(state: State<AppState>) -> Result<SyncSummary, String> {
+    // Locks the database per step, so git fetch and push don't block the UI
+    state.sync.sync(&*state.db).map_err(|e| format!("{:#}", e))
+}
+
+#[tauri::command]
+pub fn get_sync_log(limit: Option<u32>, state: State<AppState>) -> Result<Vec<SyncLog>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.get_sync_log(limit.unwrap_or(50))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn configure_sync(
+    enabled: bool,
+    interval_minutes: u32,
+    remote: String,
+    state: State<AppState>,
+) -> Result<(), String> {
+    if interval_minutes == 0 {
+        return Err("Sync interval must be at least 1 minute".to_string());
+    }
+    sync::validate_remote(remote.trim()).map_err(|e| e.to_string())?;
+
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+
+    db.set_setting("git_sync_enabled", if enabled { "true" } else { "false" })
+        .and_then(|_| db.set_setting("git_sync_interval_minutes", &interval_minutes.to_string()))
+        .and_then(|_| db.set_setting("git_sync_remote", remote.trim()))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_timezone(state: State<AppState>) -> Result<String, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.timezone()
+        .map(|tz| tz.name().to_string())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn set_timezone(timezone: String, state: State<AppState>) -> Result<String, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.set_timezone(&timezone)
+        .map(|tz| tz.name().to_string())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn list_extractors(state: State<AppState>) -> Result<Vec<nlp::ExtractorInfo>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let enabled = db.nlp_extractors().map_err(|e| e.to_string())?;
+    Ok(state.nlp.describe(enabled.as_deref()))
+}
+
+/// Set which quick-capture extractors run and in what order; `None` restores the defaults
+#[tauri::command]
+pub fn configure_extractors(
+    enabled: Option<Vec<String>>,
+    state: State<AppState>,
+) -> Result<Vec<nlp::ExtractorInfo>, String> {
+    if let Some(enabled) = &enabled {
+        let known = state.nlp.names();
+        for (i, name) in enabled.iter().enumerate() {
+            if !known.contains(&name.as_str()) {
+                return Err(format!("Unknown extractor: {}", name));
+            }
+            if enabled[..i].contains(name) {
+                return Err(format!("Extractor listed twice: {}", name));
+            }
+        }
+    }
+
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.set_nlp_extractors(enabled.as_deref())
+        .map_err(|e| e.to_string())?;
+    Ok(state.nlp.describe(enabled.as_deref()))
+}
+
+/// Record that we chased a WAITING task; the next follow-up defaults to the configured interval
+#[tauri::command]
+pub fn log_follow_up(
+    id: String,
+    note: Option<String>,
+    next_in_days: Option<i64>,
+    state: State<AppState>,
+) -> Result<Task, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.log_follow_up(&id, note.as_deref(), next_in_days)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_follow_ups(id: String, state: State<AppState>) -> Result<Vec<FollowUp>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.get_follow_ups(&id).map_err(|e| e.to_string())
+}
+
+/// NEXT tasks ranked as candidates for NOW, with the reasons behind each score
+#[tauri::command]
+pub fn suggest_now_task(
+    limit: Option<usize>,
+    state: State<AppState>,
+) -> Result<Vec<Suggestion>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.suggest_now_tasks(limit.unwrap_or(5))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn create_project(
+    title: String,
+    notes: Option<String>,
+    state: State<AppState>,
+) -> Result<Project, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.create_project(&title, notes.as_deref())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_projects(
+    status: Option<ProjectStatus>,
+    state: State<AppState>,
+) -> Result<Vec<ProjectSummary>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.get_projects(status).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_project(id: String, state: State<AppState>) -> Result<ProjectSummary, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.get_project_summary(&id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn update_project(
+    id: String,
+    patch: ProjectPatch,
+    state: State<AppState>,
+) -> Result<Project, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.update_project(&id, patch).map_err(|e| e.to_string())
+}
+
+/// Delete a project, moving its tasks to the trash
+#[tauri::command]
+pub fn delete_project(id: String, state: State<AppState>) -> Result<(), String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.delete_project(&id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_project_tasks(id: String, state: State<AppState>) -> Result<Vec<Task>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.get_project_tasks(&id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_subtasks(id: String, state: State<AppState>) -> Result<Vec<Task>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.get_subtasks(&id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_task_progress(id: String, state: State<AppState>) -> Result<Progress, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.get_task_progress(&id).map_err(|e| e.to_string())
 }
@@ -0,0 +1,394 @@
+use anyhow::{bail, Result};
+use chrono::Utc;
+use rusqlite::{params, OptionalExtension};
+
+use super::connection::Database;
+use super::history::{row_to_task_event, TASK_EVENT_COLUMNS};
+use super::models::{Backup, RestoreMode, RestoreSummary, ReviewTriage, Setting};
+use super::review::{row_to_review_log, REVIEW_COLUMNS};
+use super::waiting::{row_to_follow_up, FOLLOW_UP_COLUMNS};
+
+/// Version of the backup file layout, independent of the schema version
+pub const BACKUP_FORMAT_VERSION: u32 = 1;
+
+impl Database {
+    /// Snapshot tasks (trash included) with their follow-ups and history,
+    /// projects, settings and the review and sync logs
+    pub fn export_backup(&self) -> Result<Backup> {
+        let mut stmt = self
+            .conn()
+            .prepare("SELECT key, value, updated_at FROM settings ORDER BY key")?;
+        let settings = stmt
+            .query_map([], |row| {
+                Ok(Setting {
+                    key: row.get(0)?,
+                    value: row.get(1)?,
+                    updated_at: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut stmt = self.conn().prepare(&format!(
+            "SELECT {} FROM review_log ORDER BY started_at",
+            REVIEW_COLUMNS
+        ))?;
+        let review_log = stmt
+            .query_map([], row_to_review_log)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut stmt = self.conn().prepare(
+            "SELECT review_id, task_id, triaged_at FROM review_triage
+             ORDER BY triaged_at, rowid",
+        )?;
+        let review_triage = stmt
+            .query_map([], |row| {
+                Ok(ReviewTriage {
+                    review_id: row.get(0)?,
+                    task_id: row.get(1)?,
+                    triaged_at: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut sync_log = self.get_sync_log(u32::MAX)?;
+        sync_log.reverse();
+
+        let mut stmt = self.conn().prepare(&format!(
+            "SELECT {} FROM follow_ups ORDER BY nudged_at, rowid",
+            FOLLOW_UP_COLUMNS
+        ))?;
+        let follow_ups = stmt
+            .query_map([], row_to_follow_up)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut stmt = self.conn().prepare(&format!(
+            "SELECT {} FROM task_events ORDER BY id",
+            TASK_EVENT_COLUMNS
+        ))?;
+        let task_events = stmt
+            .query_map([], row_to_task_event)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Backup {
+            format_version: BACKUP_FORMAT_VERSION,
+            schema_version: self.schema_version()?,
+            exported_at: Utc::now().timestamp(),
+            settings,
+            projects: self.get_all_projects()?,
+            tasks: self.get_all_tasks()?,
+            review_log,
+            review_triage,
+            sync_log,
+            follow_ups,
+            task_events,
+        })
+    }
+
+    /// Load a backup in one transaction; on any error nothing is changed.
+    /// Backups from older schemas are accepted, newer ones are not.
+    pub fn restore_backup(&self, backup: &Backup, mode: RestoreMode) -> Result<RestoreSummary> {
+        if backup.format_version != BACKUP_FORMAT_VERSION {
+            bail!(
+                "Unsupported backup format version {}",
+                backup.format_version
+            );
+        }
+        let schema_version = self.schema_version()?;
+        if backup.schema_version > schema_version {
+            bail!(
+                "Backup schema version {} is newer than this database ({})",
+                backup.schema_version,
+                schema_version
+            );
+        }
+
+        let tx = self.conn().unchecked_transaction()?;
+        // Records may arrive children first; check references at the end
+        self.conn().execute("PRAGMA defer_foreign_keys = ON", [])?;
+
+        if mode == RestoreMode::Replace {
+            self.conn().execute_batch(
+                "DELETE FROM task_events;
+                 DELETE FROM follow_ups;
+                 DELETE FROM tasks;
+                 DELETE FROM projects;
+                 DELETE FROM tags;
+                 DELETE FROM settings;
+                 DELETE FROM review_triage;
+                 DELETE FROM review_log;
+                 DELETE FROM sync_log;
+                 DELETE FROM undo_journal;",
+            )?;
+        }
+
+        let mut summary = RestoreSummary::default();
+
+        for setting in &backup.settings {
+            // Merging keeps whichever value was set last
+            summary.settings += self.conn().execute(
+                "INSERT INTO settings (key, value, updated_at) VALUES (?1, ?2, ?3)
+                 ON CONFLICT(key) DO UPDATE SET
+                    value = excluded.value,
+                    updated_at = excluded.updated_at
+                 WHERE excluded.updated_at > settings.updated_at",
+                params![setting.key, setting.value, setting.updated_at],
+            )?;
+        }
+
+        for project in &backup.projects {
+            if self.is_newer(
+                "SELECT sync_version FROM projects WHERE id = ?1",
+                &project.id,
+                project.sync_version,
+            )? {
+                self.upsert_project(project)?;
+                summary.projects += 1;
+            }
+        }
+
+        // History goes in ahead of the tasks so the events the restore
+        // itself records come after it. Event ids are local, so rows are
+        // matched on their content instead.
+        for event in &backup.task_events {
+            summary.task_events += self.conn().execute(
+                "INSERT INTO task_events (task_id, field, old_value, new_value, created_at, source)
+                 SELECT ?1, ?2, ?3, ?4, ?5, ?6
+                 WHERE NOT EXISTS (
+                    SELECT 1 FROM task_events
+                    WHERE task_id = ?1 AND field = ?2 AND old_value IS ?3
+                        AND new_value IS ?4 AND created_at = ?5 AND source = ?6
+                 )",
+                params![
+                    event.task_id,
+                    event.field,
+                    event.old_value,
+                    event.new_value,
+                    event.created_at,
+                    event.source
+                ],
+            )?;
+        }
+
+        self.with_event_source("restore", |db| {
+            for task in &backup.tasks {
+                if db.is_newer(
+                    "SELECT sync_version FROM tasks WHERE id = ?1",
+                    &task.id,
+                    task.sync_version,
+                )? {
+                    db.upsert_task(task)?;
+                    summary.tasks += 1;
+                }
+            }
+            Ok(())
+        })?;
+
+        for review in &backup.review_log {
+            summary.review_log += self.conn().execute(
+                "INSERT OR IGNORE INTO review_log (id, started_at, completed_at, tasks_triaged, tasks_completed, tasks_deferred, tasks_deleted)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    review.id,
+                    review.started_at,
+                    review.completed_at,
+                    review.tasks_triaged,
+                    review.tasks_completed,
+                    review.tasks_deferred,
+                    review.tasks_deleted,
+                ],
+            )?;
+        }
+
+        for triage in &backup.review_triage {
+            summary.review_triage += self.conn().execute(
+                "INSERT OR IGNORE INTO review_triage (review_id, task_id, triaged_at)
+                 VALUES (?1, ?2, ?3)",
+                params![triage.review_id, triage.task_id, triage.triaged_at],
+            )?;
+        }
+
+        for log in &backup.sync_log {
+            summary.sync_log += self.conn().execute(
+                "INSERT OR IGNORE INTO sync_log (id, timestamp, operation, status, message)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    log.id,
+                    log.timestamp,
+                    log.operation,
+                    log.status,
+                    log.message
+                ],
+            )?;
+        }
+
+        for follow_up in &backup.follow_ups {
+            summary.follow_ups += self.conn().execute(
+                "INSERT OR IGNORE INTO follow_ups (id, task_id, nudged_at, note)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    follow_up.id,
+                    follow_up.task_id,
+                    follow_up.nudged_at,
+                    follow_up.note
+                ],
+            )?;
+        }
+
+        let broken: Option<String> = self
+            .conn()
+            .query_row("PRAGMA foreign_key_check", [], |row| row.get(0))
+            .optional()?;
+        if let Some(table) = broken {
+            bail!("Backup has {} that point at missing records", table);
+        }
+
+        tx.commit()?;
+        Ok(summary)
+    }
+
+    /// Helper: Whether a record at `sync_version` should overwrite the stored
+    /// one (or there is none)
+    fn is_newer(&self, query: &str, id: &str, sync_version: i32) -> Result<bool> {
+        let current: Option<i32> = self
+            .conn()
+            .query_row(query, params![id], |row| row.get(0))
+            .optional()?;
+        Ok(current.is_none_or(|current| sync_version > current))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{NewTask, ReviewDecision, TaskStatus};
+    use tempfile::{tempdir, TempDir};
+
+    fn setup_test_db() -> (TempDir, Database) {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let db = Database::new(db_path).unwrap();
+        (dir, db)
+    }
+
+    fn insert(db: &Database, title: &str, project_id: Option<&str>) -> String {
+        db.insert_task(&NewTask {
+            title: title.to_string(),
+            status: TaskStatus::Next,
+            source: "test".to_string(),
+            tags: vec!["home".to_string()],
+            project_id: project_id.map(str::to_string),
+            ..Default::default()
+        })
+        .unwrap()
+        .id
+    }
+
+    #[test]
+    fn test_replace_round_trip() {
+        let (_dir, db) = setup_test_db();
+        let project = db.create_project("Garden", None).unwrap();
+        let kept = insert(&db, "Plant tulips", Some(&project.id));
+        let trashed = insert(&db, "Old idea", None);
+        db.delete_task(&trashed).unwrap();
+        let waiting = insert(&db, "Chase quote", None);
+        db.update_task_status(&waiting, TaskStatus::Waiting)
+            .unwrap();
+        db.log_follow_up(&waiting, Some("Emailed"), None).unwrap();
+        db.set_timezone("Europe/Berlin").unwrap();
+        let review = db.start_review().unwrap();
+        db.record_review_decision(&review.id, &kept, ReviewDecision::Keep)
+            .unwrap();
+        db.log_sync("push", "success", None).unwrap();
+
+        let json = serde_json::to_string(&db.export_backup().unwrap()).unwrap();
+
+        let (_dir2, other) = setup_test_db();
+        insert(&other, "Goes away", None);
+        let backup: Backup = serde_json::from_str(&json).unwrap();
+        let summary = other.restore_backup(&backup, RestoreMode::Replace).unwrap();
+
+        assert_eq!(summary.tasks, 3);
+        assert_eq!(summary.projects, 1);
+        assert_eq!(summary.follow_ups, 1);
+        assert_eq!(summary.review_log, 1);
+        assert_eq!(summary.review_triage, 1);
+        assert_eq!(summary.sync_log, 1);
+        assert_eq!(other.get_all_tasks().unwrap(), db.get_all_tasks().unwrap());
+        assert_eq!(other.get_task(&kept).unwrap().tags, vec!["home"]);
+        assert_eq!(other.get_trash().unwrap().len(), 1);
+        assert_eq!(
+            other.get_follow_ups(&waiting).unwrap()[0].note.as_deref(),
+            Some("Emailed")
+        );
+
+        // History comes back ahead of the events the restore itself adds
+        let fields = |db: &Database| -> Vec<(String, String)> {
+            db.get_task_history(&waiting)
+                .unwrap()
+                .into_iter()
+                .map(|event| (event.field, event.source))
+                .collect()
+        };
+        let original = fields(&db);
+        let restored = fields(&other);
+        assert_eq!(restored[..original.len()], original[..]);
+        assert!(restored[original.len()..]
+            .iter()
+            .all(|(_, source)| source == "restore"));
+
+        // Restoring again doesn't duplicate anything
+        let summary = other.restore_backup(&backup, RestoreMode::Merge).unwrap();
+        assert_eq!((summary.follow_ups, summary.task_events), (0, 0));
+        assert_eq!(
+            other.timezone().unwrap(),
+            "Europe/Berlin".parse::<chrono_tz::Tz>().unwrap()
+        );
+        assert!(other.get_active_review().unwrap().is_some());
+    }
+
+    #[test]
+    fn test_merge_keeps_newer() {
+        let (_dir, db) = setup_test_db();
+        let id = insert(&db, "Original", None);
+        let mut backup = db.export_backup().unwrap();
+        db.update_task_status(&id, TaskStatus::Done).unwrap();
+
+        // The local copy has moved on; the backup's older copy is ignored
+        backup.tasks[0].title = "Stale".to_string();
+        let summary = db.restore_backup(&backup, RestoreMode::Merge).unwrap();
+        assert_eq!(summary.tasks, 0);
+        let task = db.get_task(&id).unwrap();
+        assert_eq!(
+            (task.title.as_str(), task.status),
+            ("Original", TaskStatus::Done)
+        );
+
+        // Tasks only the backup has are added
+        backup.tasks[0].id = "from-backup".to_string();
+        let summary = db.restore_backup(&backup, RestoreMode::Merge).unwrap();
+        assert_eq!(summary.tasks, 1);
+        assert_eq!(db.get_task("from-backup").unwrap().title, "Stale");
+        assert_eq!(db.get_all_tasks().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_rejects_bad_backups() {
+        let (_dir, db) = setup_test_db();
+        let id = insert(&db, "Keep me", None);
+        let mut backup = db.export_backup().unwrap();
+
+        let mut newer = backup.clone();
+        newer.schema_version += 1;
+        assert!(db.restore_backup(&newer, RestoreMode::Replace).is_err());
+
+        let mut unknown = backup.clone();
+        unknown.format_version = 99;
+        assert!(db.restore_backup(&unknown, RestoreMode::Replace).is_err());
+
+        // A task pointing at a project that isn't there fails the whole restore
+        backup.tasks[0].project_id = Some("missing".to_string());
+        assert!(db.restore_backup(&backup, RestoreMode::Replace).is_err());
+        assert_eq!(db.get_task(&id).unwrap().title, "Keep me");
+        assert_eq!(db.get_all_tasks().unwrap().len(), 1);
+    }
+}
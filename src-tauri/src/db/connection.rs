@@ -1,7 +1,9 @@
-use anyhow::Result;
-use rusqlite::{params, Connection};
+use anyhow::{bail, Context, Result};
+use rusqlite::{params, Connection, DatabaseName, OpenFlags};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use super::migrations::{self, Migration};
 
 pub struct Database {
     conn: Connection,
@@ -23,12 +25,89 @@ impl Database {
         let mut db = Database { conn };
         db.run_migrations()?;
 
+        // A run that died mid-sync may have left changes attributed elsewhere
+        db.set_event_source("app")?;
+
         Ok(db)
     }
 
-    /// Run database migrations
+    /// Apply every pending migration, each in its own transaction.
+    ///
+    /// Refuses to continue if an applied migration was edited after the fact
+    /// or the database was written by a newer build.
     fn run_migrations(&mut self) -> Result<()> {
-        // Create migrations table if it doesn't exist
+        self.ensure_migrations_table()?;
+
+        let known = migrations::all()?;
+        let current_version = self.schema_version()?;
+        let latest_version = known.last().map(|m| m.version).unwrap_or(0);
+
+        if current_version > latest_version {
+            bail!(
+                "Database schema version {} is newer than this build supports ({})",
+                current_version,
+                latest_version
+            );
+        }
+
+        self.verify_checksums(&known)?;
+
+        for migration in known.iter().filter(|m| m.version > current_version) {
+            let tx = self.conn.transaction()?;
+            tx.execute_batch(migration.up)?;
+            tx.execute(
+                "INSERT INTO migrations (version, applied_at, name, checksum)
+                 VALUES (?1, strftime('%s', 'now'), ?2, ?3)",
+                params![migration.version, migration.name, migration.checksum()],
+            )?;
+            tx.commit()?;
+
+            println!("Applied migration {}", migration.name);
+        }
+
+        Ok(())
+    }
+
+    /// Revert applied migrations, newest first, down to `target_version`
+    pub fn rollback_to(&mut self, target_version: i32) -> Result<()> {
+        let known = migrations::all()?;
+        let current_version = self.schema_version()?;
+
+        for version in ((target_version + 1)..=current_version).rev() {
+            let Some(migration) = known.iter().find(|m| m.version == version) else {
+                bail!("Migration {} is not known to this build", version);
+            };
+            let Some(down) = migration.down else {
+                bail!("Migration {} cannot be rolled back", migration.name);
+            };
+
+            let tx = self.conn.transaction()?;
+            tx.execute_batch(down)?;
+            tx.execute(
+                "DELETE FROM migrations WHERE version = ?1",
+                params![version],
+            )?;
+            tx.commit()?;
+
+            println!("Rolled back migration {}", migration.name);
+        }
+
+        Ok(())
+    }
+
+    /// Highest migration version applied to this database
+    pub fn schema_version(&self) -> Result<i32> {
+        let version = self.conn.query_row(
+            "SELECT COALESCE(MAX(version), 0) FROM migrations",
+            [],
+            |row| row.get(0),
+        )?;
+
+        Ok(version)
+    }
+
+    /// Create the migrations table, upgrading the original two-column layout
+    fn ensure_migrations_table(&self) -> Result<()> {
         self.conn.execute(
             "CREATE TABLE IF NOT EXISTS migrations (
                 version INTEGER PRIMARY KEY,
@@ -37,38 +116,120 @@ impl Database {
             [],
         )?;
 
-        // Check current version
-        let current_version: i32 = self
+        let columns: Vec<String> = self
             .conn
-            .query_row(
-                "SELECT COALESCE(MAX(version), 0) FROM migrations",
-                [],
-                |row| row.get(0),
-            )
-            .unwrap_or(0);
+            .prepare("SELECT name FROM pragma_table_info('migrations')")?
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
 
-        // Migration 1: Initial schema
-        if current_version < 1 {
-            let migration_sql = include_str!("../../migrations/001_initial_schema.sql");
-            self.conn.execute_batch(migration_sql)?;
+        for column in ["name", "checksum"] {
+            if !columns.iter().any(|c| c == column) {
+                self.conn.execute(
+                    &format!("ALTER TABLE migrations ADD COLUMN {} TEXT", column),
+                    [],
+                )?;
+            }
+        }
 
-            self.conn.execute(
-                "INSERT INTO migrations (version, applied_at) VALUES (?1, strftime('%s', 'now'))",
-                params![1],
-            )?;
+        Ok(())
+    }
+
+    /// Compare recorded checksums with the embedded scripts. Rows recorded
+    /// before checksums existed are filled in with the current checksum.
+    fn verify_checksums(&self, known: &[Migration]) -> Result<()> {
+        let applied: Vec<(i32, Option<String>)> = self
+            .conn
+            .prepare("SELECT version, checksum FROM migrations ORDER BY version")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_, _>>()?;
+
+        for (version, checksum) in applied {
+            let Some(migration) = known.iter().find(|m| m.version == version) else {
+                continue;
+            };
+
+            match checksum {
+                Some(checksum) if checksum != migration.checksum() => bail!(
+                    "Migration {} was modified after it was applied",
+                    migration.name
+                ),
+                Some(_) => {}
+                None => {
+                    self.conn.execute(
+                        "UPDATE migrations SET name = ?1, checksum = ?2 WHERE version = ?3",
+                        params![migration.name, migration.checksum(), version],
+                    )?;
+                }
+            }
+        }
+
+        Ok(())
+    }
 
-            println!("Applied migration 001_initial_schema.sql");
+    /// Copy the database to `path` with SQLite's online backup API and
+    /// check the copy. A copy that fails the integrity check is removed.
+    pub fn snapshot_to(&self, path: &Path) -> Result<()> {
+        self.conn.backup(DatabaseName::Main, path, None)?;
+
+        if let Err(e) = check_snapshot(path) {
+            let _ = fs::remove_file(path);
+            return Err(e);
         }
 
         Ok(())
     }
 
+    /// Replace the whole database with a snapshot, then migrate it to this
+    /// build's schema. The snapshot is checked before anything is touched.
+    pub fn restore_from(&mut self, path: &Path) -> Result<()> {
+        let version = check_snapshot(path)?;
+        let latest_version = migrations::latest_version();
+        if version > latest_version {
+            bail!(
+                "Snapshot schema version {} is newer than this build supports ({})",
+                version,
+                latest_version
+            );
+        }
+
+        self.conn.restore(
+            DatabaseName::Main,
+            path,
+            None::<fn(rusqlite::backup::Progress)>,
+        )?;
+        self.run_migrations()?;
+        self.set_event_source("app")?;
+
+        Ok(())
+    }
+
     /// Get database connection reference
     pub fn conn(&self) -> &Connection {
         &self.conn
     }
 }
 
+/// Run SQLite's integrity check on a database file and return its schema version
+pub fn check_snapshot(path: &Path) -> Result<i32> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .with_context(|| format!("Cannot open snapshot {}", path.display()))?;
+
+    let result: String = conn.query_row("PRAGMA integrity_check", [], |row| row.get(0))?;
+    if result != "ok" {
+        bail!("Snapshot {} is corrupt: {}", path.display(), result);
+    }
+
+    let version = conn
+        .query_row(
+            "SELECT COALESCE(MAX(version), 0) FROM migrations",
+            [],
+            |row| row.get(0),
+        )
+        .with_context(|| format!("{} is not a TaskFlow database", path.display()))?;
+
+    Ok(version)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -100,12 +261,139 @@ mod tests {
 
         let db = Database::new(db_path).unwrap();
 
-        // Verify migration was applied
+        // Verify every migration was applied and checksummed
         let version: i32 = db
             .conn()
             .query_row("SELECT MAX(version) FROM migrations", [], |row| row.get(0))
             .unwrap();
 
-        assert_eq!(version, 1);
+        assert_eq!(version, migrations::latest_version());
+
+        let missing_checksums: i32 = db
+            .conn()
+            .query_row(
+                "SELECT COUNT(*) FROM migrations WHERE checksum IS NULL OR name IS NULL",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+
+        assert_eq!(missing_checksums, 0);
+    }
+
+    #[test]
+    fn test_refuses_edited_migration() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+
+        let db = Database::new(db_path.clone()).unwrap();
+        db.conn()
+            .execute(
+                "UPDATE migrations SET checksum = 'edited' WHERE version = 1",
+                [],
+            )
+            .unwrap();
+        drop(db);
+
+        let err = Database::new(db_path).err().unwrap();
+        assert!(err.to_string().contains("modified"));
+    }
+
+    #[test]
+    fn test_refuses_newer_database() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+
+        let db = Database::new(db_path.clone()).unwrap();
+        db.conn()
+            .execute(
+                "INSERT INTO migrations (version, applied_at) VALUES (9999, 0)",
+                [],
+            )
+            .unwrap();
+        drop(db);
+
+        let err = Database::new(db_path).err().unwrap();
+        assert!(err.to_string().contains("newer"));
+    }
+
+    #[test]
+    fn test_backfills_legacy_migrations_table() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+
+        // A database created by the original runner: no name/checksum columns
+        let conn = Connection::open(&db_path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE migrations (version INTEGER PRIMARY KEY, applied_at INTEGER NOT NULL);",
+        )
+        .unwrap();
+        conn.execute_batch(migrations::all().unwrap()[0].up)
+            .unwrap();
+        conn.execute(
+            "INSERT INTO migrations (version, applied_at) VALUES (1, 0)",
+            [],
+        )
+        .unwrap();
+        drop(conn);
+
+        let db = Database::new(db_path).unwrap();
+        assert_eq!(db.schema_version().unwrap(), migrations::latest_version());
+
+        let checksum: String = db
+            .conn()
+            .query_row(
+                "SELECT checksum FROM migrations WHERE version = 1",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(checksum, migrations::all().unwrap()[0].checksum());
+    }
+
+    #[test]
+    fn test_rollback_and_reapply() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+
+        let mut db = Database::new(db_path.clone()).unwrap();
+        db.conn()
+            .execute(
+                "INSERT INTO tasks (id, title, created_at, updated_at, source) VALUES ('t1', 'Task', 0, 0, 'test')",
+                [],
+            )
+            .unwrap();
+        db.set_task_tags("t1", &["work".to_string(), "home".to_string()])
+            .unwrap();
+
+        // Back to the free-text tags column
+        db.rollback_to(2).unwrap();
+        assert_eq!(db.schema_version().unwrap(), 2);
+        let tags: String = db
+            .conn()
+            .query_row("SELECT tags FROM tasks WHERE id = 't1'", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        let mut tags: Vec<&str> = tags.split(',').collect();
+        tags.sort();
+        assert_eq!(tags, vec!["home", "work"]);
+        drop(db);
+
+        // Reopening migrates forward again and keeps the tags
+        let db = Database::new(db_path).unwrap();
+        assert_eq!(db.get_task("t1").unwrap().tags, vec!["home", "work"]);
+
+        let mut db = db;
+        db.rollback_to(0).unwrap();
+        let tables: i32 = db
+            .conn()
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name != 'migrations' AND name NOT LIKE 'sqlite_%'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(tables, 0);
     }
 }
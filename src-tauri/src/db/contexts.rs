@@ -0,0 +1,154 @@
+use anyhow::{bail, Result};
+
+use super::connection::Database;
+use super::models::ContextCount;
+
+/// Normalize a context name: strip a leading '@'; no whitespace allowed
+pub fn normalize_context(context: &str) -> Result<String> {
+    let context = context.trim().trim_start_matches('@');
+    if context.is_empty() || context.contains(char::is_whitespace) {
+        bail!("Invalid context: {:?}", context);
+    }
+    Ok(context.to_string())
+}
+
+impl Database {
+    /// The context the user is working in, if any
+    pub fn current_context(&self) -> Result<Option<String>> {
+        Ok(self
+            .get_setting("current_context")?
+            .filter(|context| !context.is_empty()))
+    }
+
+    /// Switch to a context, or back to all contexts with `None`
+    pub fn set_current_context(&self, context: Option<&str>) -> Result<Option<String>> {
+        let context = context.map(normalize_context).transpose()?;
+        self.set_setting("current_context", context.as_deref().unwrap_or(""))?;
+        Ok(context)
+    }
+
+    /// Every context in use with task counts, busiest first. Contexts that
+    /// differ only in case are counted together.
+    pub fn list_contexts(&self) -> Result<Vec<ContextCount>> {
+        let mut stmt = self.conn().prepare(
+            "SELECT MIN(context),
+                    COUNT(*),
+                    COUNT(CASE WHEN status != 'done' THEN 1 END) AS open_count
+             FROM tasks
+             WHERE context IS NOT NULL AND deleted_at IS NULL
+             GROUP BY context COLLATE NOCASE
+             ORDER BY open_count DESC, MIN(context) COLLATE NOCASE ASC",
+        )?;
+
+        let contexts = stmt
+            .query_map([], |row| {
+                Ok(ContextCount {
+                    name: row.get(0)?,
+                    task_count: row.get(1)?,
+                    open_task_count: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(contexts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{NewTask, TaskFilter, TaskQuery, TaskStatus};
+    use tempfile::{tempdir, TempDir};
+
+    fn setup_test_db() -> (TempDir, Database) {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let db = Database::new(db_path).unwrap();
+        (dir, db)
+    }
+
+    fn insert(db: &Database, title: &str, status: TaskStatus, context: Option<&str>) {
+        db.insert_task(&NewTask {
+            title: title.to_string(),
+            status,
+            context: context.map(str::to_string),
+            source: "test".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn test_set_current_context() {
+        let (_dir, db) = setup_test_db();
+        assert_eq!(db.current_context().unwrap(), None);
+
+        assert_eq!(
+            db.set_current_context(Some(" @office ")).unwrap(),
+            Some("office".to_string())
+        );
+        assert_eq!(db.current_context().unwrap(), Some("office".to_string()));
+
+        assert!(db.set_current_context(Some("two words")).is_err());
+        assert!(db.set_current_context(Some("@")).is_err());
+        assert_eq!(db.current_context().unwrap(), Some("office".to_string()));
+
+        db.set_current_context(None).unwrap();
+        assert_eq!(db.current_context().unwrap(), None);
+    }
+
+    #[test]
+    fn test_list_contexts() {
+        let (_dir, db) = setup_test_db();
+        insert(&db, "Print slides", TaskStatus::Next, Some("office"));
+        insert(&db, "Book room", TaskStatus::Done, Some("Office"));
+        insert(&db, "Buy stamps", TaskStatus::Next, Some("errands"));
+        insert(&db, "Return parcel", TaskStatus::Waiting, Some("errands"));
+        insert(&db, "Think", TaskStatus::Next, None);
+
+        let contexts = db.list_contexts().unwrap();
+        let summary: Vec<_> = contexts
+            .iter()
+            .map(|c| (c.name.to_lowercase(), c.task_count, c.open_task_count))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("errands".to_string(), 2, 2), ("office".to_string(), 2, 1)]
+        );
+    }
+
+    #[test]
+    fn test_context_mode_filters_views() {
+        let (_dir, db) = setup_test_db();
+        insert(&db, "Print slides", TaskStatus::Next, Some("Office"));
+        insert(&db, "Buy stamps", TaskStatus::Next, Some("errands"));
+        insert(&db, "Think", TaskStatus::Next, None);
+        insert(&db, "Hear from IT", TaskStatus::Waiting, Some("office"));
+        insert(&db, "Hear from shop", TaskStatus::Waiting, Some("errands"));
+
+        let dashboard = db.get_dashboard_data().unwrap();
+        assert_eq!(dashboard.next_tasks.len(), 3);
+        assert_eq!(dashboard.waiting_tasks.len(), 2);
+        assert_eq!(dashboard.current_context, None);
+
+        db.set_current_context(Some("office")).unwrap();
+        let dashboard = db.get_dashboard_data().unwrap();
+        let mut next: Vec<_> = dashboard.next_tasks.iter().map(|t| &t.title).collect();
+        next.sort();
+        assert_eq!(next, vec!["Print slides", "Think"]);
+        assert_eq!(dashboard.waiting_tasks.len(), 1);
+        assert_eq!(dashboard.waiting_tasks[0].task.title, "Hear from IT");
+        assert_eq!(dashboard.current_context, Some("office".to_string()));
+
+        // Listings only follow context mode when asked to
+        assert_eq!(db.list_tasks(&TaskQuery::default()).unwrap().tasks.len(), 5);
+        let query = TaskQuery {
+            filter: TaskFilter {
+                in_current_context: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(db.list_tasks(&query).unwrap().tasks.len(), 3);
+    }
+}
@@ -0,0 +1,180 @@
+use anyhow::Result;
+use rusqlite::{params, Row};
+
+use super::connection::Database;
+use super::models::TaskEvent;
+
+pub(crate) const TASK_EVENT_COLUMNS: &str =
+    "id, task_id, field, old_value, new_value, created_at, source";
+
+impl Database {
+    /// Every recorded change to a task, oldest first
+    pub fn get_task_history(&self, task_id: &str) -> Result<Vec<TaskEvent>> {
+        let mut stmt = self.conn().prepare(&format!(
+            "SELECT {} FROM task_events WHERE task_id = ?1 ORDER BY id",
+            TASK_EVENT_COLUMNS
+        ))?;
+
+        let events = stmt
+            .query_map(params![task_id], row_to_task_event)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(events)
+    }
+
+    /// Run `f` with task changes attributed to `source` instead of the user
+    pub(crate) fn with_event_source<T>(
+        &self,
+        source: &str,
+        f: impl FnOnce(&Self) -> Result<T>,
+    ) -> Result<T> {
+        let previous: String = self.conn().query_row(
+            "SELECT source FROM task_event_source WHERE id = 1",
+            [],
+            |row| row.get(0),
+        )?;
+
+        self.set_event_source(source)?;
+        let result = f(self);
+        self.set_event_source(&previous)?;
+
+        result
+    }
+
+    /// Attribute task changes to `source` until told otherwise
+    pub(crate) fn set_event_source(&self, source: &str) -> Result<()> {
+        self.conn().execute(
+            "UPDATE task_event_source SET source = ?1 WHERE id = 1",
+            params![source],
+        )?;
+        Ok(())
+    }
+}
+
+/// Helper: Convert database row to TaskEvent
+pub(crate) fn row_to_task_event(row: &Row) -> rusqlite::Result<TaskEvent> {
+    Ok(TaskEvent {
+        id: row.get(0)?,
+        task_id: row.get(1)?,
+        field: row.get(2)?,
+        old_value: row.get(3)?,
+        new_value: row.get(4)?,
+        created_at: row.get(5)?,
+        source: row.get(6)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{NewTask, TaskPatch, TaskStatus};
+    use tempfile::{tempdir, TempDir};
+
+    fn setup_test_db() -> (TempDir, Database) {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let db = Database::new(db_path).unwrap();
+        (dir, db)
+    }
+
+    fn changes(events: &[TaskEvent], field: &str) -> Vec<(Option<String>, Option<String>)> {
+        events
+            .iter()
+            .filter(|event| event.field == field)
+            .map(|event| (event.old_value.clone(), event.new_value.clone()))
+            .collect()
+    }
+
+    fn value(s: &str) -> Option<String> {
+        Some(s.to_string())
+    }
+
+    #[test]
+    fn test_status_transitions() {
+        let (_dir, db) = setup_test_db();
+        let task = db
+            .insert_task(&NewTask {
+                title: "Call plumber".to_string(),
+                status: TaskStatus::Next,
+                source: "test".to_string(),
+                tags: vec!["home".to_string()],
+                ..Default::default()
+            })
+            .unwrap();
+
+        db.update_task_status(&task.id, TaskStatus::Waiting)
+            .unwrap();
+        db.update_task_status(&task.id, TaskStatus::Next).unwrap();
+        db.update_task(
+            &task.id,
+            TaskPatch {
+                title: Some("Call the plumber".to_string()),
+                tags: Some(vec!["home".to_string(), "phone".to_string()]),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let history = db.get_task_history(&task.id).unwrap();
+        assert_eq!(history[0].field, "created");
+        assert_eq!(history[0].new_value, value("next"));
+        assert!(history.iter().all(|event| event.source == "app"));
+        assert_eq!(
+            changes(&history, "status"),
+            vec![
+                (value("next"), value("waiting")),
+                (value("waiting"), value("next")),
+            ]
+        );
+        assert_eq!(
+            changes(&history, "title"),
+            vec![(value("Call plumber"), value("Call the plumber"))]
+        );
+        // Re-setting the tags records only the one that was added
+        assert_eq!(
+            changes(&history, "tags"),
+            vec![(None, value("home")), (None, value("phone"))]
+        );
+    }
+
+    #[test]
+    fn test_demotion_and_sources() {
+        let (_dir, db) = setup_test_db();
+        let first = db
+            .insert_task(&NewTask {
+                title: "First".to_string(),
+                status: TaskStatus::Now,
+                source: "test".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+
+        db.with_event_source("sync", |db| {
+            db.insert_task(&NewTask {
+                title: "Second".to_string(),
+                status: TaskStatus::Now,
+                source: "test".to_string(),
+                ..Default::default()
+            })
+        })
+        .unwrap();
+
+        // The trigger that enforces one NOW task leaves a trace too
+        let history = db.get_task_history(&first.id).unwrap();
+        let demotion = history.last().unwrap();
+        assert_eq!(demotion.field, "status");
+        assert_eq!(demotion.new_value, value("next"));
+        assert_eq!(demotion.source, "sync");
+
+        // The source goes back once the work is done
+        db.delete_task(&first.id).unwrap();
+        let history = db.get_task_history(&first.id).unwrap();
+        let deletion = history.last().unwrap();
+        assert_eq!(deletion.field, "deleted_at");
+        assert_eq!(deletion.source, "app");
+
+        // Purging takes the history with it
+        db.purge_task(&first.id).unwrap();
+        assert!(db.get_task_history(&first.id).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,451 @@
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use super::connection::Database;
+use super::models::{JournalEntry, JournalOperation, Task, UndoState};
+use super::queries::TASK_COLUMNS;
+
+/// Most entries kept in the journal; older ones can no longer be undone
+const JOURNAL_LIMIT: i64 = 100;
+
+/// One task's state on either side of a mutation (`None` = didn't exist)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TaskChange {
+    id: String,
+    before: Option<Task>,
+    after: Option<Task>,
+}
+
+impl Database {
+    /// Run a task mutation and journal the state of every task it touched,
+    /// so it can be undone. That covers the task itself, its subtasks, the
+    /// NOW task a trigger may demote and any task the mutation creates.
+    pub fn journaled<T>(
+        &self,
+        operation: JournalOperation,
+        task_id: Option<&str>,
+        mutation: impl FnOnce(&Self) -> Result<T>,
+    ) -> Result<T> {
+        let tx = self.conn().unchecked_transaction()?;
+
+        let last_rowid: i64 =
+            self.conn()
+                .query_row("SELECT COALESCE(MAX(rowid), 0) FROM tasks", [], |row| {
+                    row.get(0)
+                })?;
+
+        let mut ids = self.task_ids(
+            "WITH RECURSIVE subtree(id) AS (
+                SELECT id FROM tasks WHERE id = ?1
+                UNION
+                SELECT tasks.id FROM tasks JOIN subtree ON tasks.parent_id = subtree.id
+             )
+             SELECT id FROM subtree
+             UNION ALL
+             SELECT id FROM tasks WHERE status = 'now'",
+            params![task_id],
+        )?;
+        let mut seen = HashSet::new();
+        ids.retain(|id| seen.insert(id.clone()));
+        let before = ids
+            .iter()
+            .map(|id| self.snapshot(id))
+            .collect::<Result<Vec<_>>>()?;
+
+        let result = mutation(self)?;
+
+        let created = self.task_ids(
+            "SELECT id FROM tasks WHERE rowid > ?1 ORDER BY rowid",
+            params![last_rowid],
+        )?;
+        let mut changes = Vec::new();
+        for (id, before) in ids.into_iter().zip(before) {
+            let after = self.snapshot(&id)?;
+            if before != after {
+                changes.push(TaskChange { id, before, after });
+            }
+        }
+        for id in created {
+            if !changes.iter().any(|change| change.id == id) {
+                let after = self.snapshot(&id)?;
+                changes.push(TaskChange {
+                    id,
+                    before: None,
+                    after,
+                });
+            }
+        }
+
+        if !changes.is_empty() {
+            let task_id = task_id
+                .map(str::to_string)
+                .or_else(|| changes.last().map(|change| change.id.clone()));
+
+            // A new mutation starts a new history; whatever was undone is gone
+            self.conn()
+                .execute("DELETE FROM undo_journal WHERE undone = 1", [])?;
+            self.conn().execute(
+                "INSERT INTO undo_journal (operation, task_id, changes, created_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    operation.as_str(),
+                    task_id,
+                    serde_json::to_string(&changes)?,
+                    Utc::now().timestamp(),
+                ],
+            )?;
+            self.conn().execute(
+                "DELETE FROM undo_journal WHERE id <= (SELECT MAX(id) FROM undo_journal) - ?1",
+                params![JOURNAL_LIMIT],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(result)
+    }
+
+    /// The mutations undo and redo would act on next
+    pub fn undo_state(&self) -> Result<UndoState> {
+        Ok(UndoState {
+            undo: self.next_entry(false)?.map(|(entry, _)| entry),
+            redo: self.next_entry(true)?.map(|(entry, _)| entry),
+        })
+    }
+
+    /// Revert the most recent journaled mutation. Returns `None` when there
+    /// is nothing left to undo.
+    pub fn undo(&self) -> Result<Option<JournalEntry>> {
+        self.replay(false)
+    }
+
+    /// Re-apply the most recently undone mutation. Returns `None` when there
+    /// is nothing to redo.
+    pub fn redo(&self) -> Result<Option<JournalEntry>> {
+        self.replay(true)
+    }
+
+    /// Undo (`redo == false`) or redo the next entry on the stack. Task content
+    /// is restored exactly; sync_version and updated_at move forward so the
+    /// change syncs like any other edit.
+    fn replay(&self, redo: bool) -> Result<Option<JournalEntry>> {
+        let Some((entry, changes)) = self.next_entry(redo)? else {
+            return Ok(None);
+        };
+
+        let tx = self.conn().unchecked_transaction()?;
+
+        // Refuse to clobber anything edited since the journaled state
+        for change in &changes {
+            let expected = if redo { &change.before } else { &change.after };
+            let current = self.snapshot(&change.id)?;
+            let unchanged = match (&current, expected) {
+                (Some(current), Some(expected)) => same_content(current, expected),
+                (None, None) => true,
+                _ => false,
+            };
+            if !unchanged {
+                let title = current
+                    .as_ref()
+                    .or(expected.as_ref())
+                    .map_or(change.id.as_str(), |task| task.title.as_str());
+                bail!(
+                    "Task \"{}\" has changed since; cannot {}",
+                    title,
+                    if redo { "redo" } else { "undo" }
+                );
+            }
+        }
+
+        let now = Utc::now().timestamp();
+
+        // Bring tasks back parent first, then remove tasks child first
+        self.with_event_source(if redo { "redo" } else { "undo" }, |db| {
+            for change in &changes {
+                let target = if redo { &change.after } else { &change.before };
+                if let Some(task) = target {
+                    let current_version = db.snapshot(&change.id)?.map(|t| t.sync_version);
+                    let mut task = task.clone();
+                    task.sync_version = current_version.unwrap_or(task.sync_version) + 1;
+                    task.updated_at = now;
+                    db.upsert_task(&task)?;
+                }
+            }
+            for change in changes.iter().rev() {
+                let target = if redo { &change.after } else { &change.before };
+                if target.is_none() {
+                    db.remove_task(&change.id)?;
+                }
+            }
+            Ok(())
+        })?;
+
+        self.conn().execute(
+            "UPDATE undo_journal SET undone = ?1 WHERE id = ?2",
+            params![!redo, entry.id],
+        )?;
+
+        tx.commit()?;
+        Ok(Some(entry))
+    }
+
+    /// Top of the undo stack (newest live entry) or of the redo stack
+    /// (oldest undone entry)
+    fn next_entry(&self, undone: bool) -> Result<Option<(JournalEntry, Vec<TaskChange>)>> {
+        let order = if undone { "ASC" } else { "DESC" };
+        let row = self
+            .conn()
+            .query_row(
+                &format!(
+                    "SELECT id, operation, task_id, changes, created_at FROM undo_journal
+                     WHERE undone = ?1 ORDER BY id {} LIMIT 1",
+                    order
+                ),
+                params![undone],
+                row_to_journal,
+            )
+            .optional()?;
+
+        let Some((id, operation, task_id, changes, created_at)) = row else {
+            return Ok(None);
+        };
+        let operation = JournalOperation::parse(&operation)
+            .with_context(|| format!("Unknown journal operation {}", operation))?;
+        let changes: Vec<TaskChange> = serde_json::from_str(&changes)
+            .with_context(|| format!("Invalid journal entry {}", id))?;
+
+        let task_title = changes
+            .iter()
+            .find(|change| Some(&change.id) == task_id.as_ref())
+            .and_then(|change| change.after.as_ref().or(change.before.as_ref()))
+            .map(|task| task.title.clone());
+
+        let entry = JournalEntry {
+            id,
+            operation,
+            task_id,
+            task_title,
+            created_at,
+        };
+        Ok(Some((entry, changes)))
+    }
+
+    /// A task as stored, including one in the trash
+    fn snapshot(&self, id: &str) -> Result<Option<Task>> {
+        let task = self
+            .conn()
+            .query_row(
+                &format!("SELECT {} FROM tasks WHERE id = ?1", TASK_COLUMNS),
+                params![id],
+                |row| self.row_to_task(row),
+            )
+            .optional()?;
+
+        Ok(task)
+    }
+
+    fn task_ids(&self, sql: &str, params: impl rusqlite::Params) -> Result<Vec<String>> {
+        let mut stmt = self.conn().prepare(sql)?;
+        let ids = stmt
+            .query_map(params, |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        Ok(ids)
+    }
+}
+
+/// Equal apart from sync bookkeeping, which undo and redo move forward
+fn same_content(a: &Task, b: &Task) -> bool {
+    let mut a = a.clone();
+    a.sync_version = b.sync_version;
+    a.updated_at = b.updated_at;
+    a == *b
+}
+
+type JournalRow = (i64, String, Option<String>, String, i64);
+
+/// Helper: Convert database row to the raw journal columns
+fn row_to_journal(row: &Row) -> rusqlite::Result<JournalRow> {
+    Ok((
+        row.get(0)?,
+        row.get(1)?,
+        row.get(2)?,
+        row.get(3)?,
+        row.get(4)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{NewTask, TaskPatch, TaskStatus};
+    use tempfile::{tempdir, TempDir};
+
+    fn setup_test_db() -> (TempDir, Database) {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let db = Database::new(db_path).unwrap();
+        (dir, db)
+    }
+
+    fn create(db: &Database, title: &str, status: TaskStatus) -> Task {
+        db.journaled(JournalOperation::CreateTask, None, |db| {
+            db.insert_task(&NewTask {
+                title: title.to_string(),
+                status,
+                source: "test".to_string(),
+                ..Default::default()
+            })
+        })
+        .unwrap()
+    }
+
+    fn set_status(db: &Database, id: &str, status: TaskStatus) {
+        db.journaled(JournalOperation::UpdateTaskStatus, Some(id), |db| {
+            db.update_task_status(id, status)
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn test_undo_status_change_restores_demoted_now() {
+        let (_dir, db) = setup_test_db();
+        let current = create(&db, "Current focus", TaskStatus::Now);
+        let next = create(&db, "Next up", TaskStatus::Next);
+
+        set_status(&db, &next.id, TaskStatus::Now);
+        assert_eq!(db.get_task(&current.id).unwrap().status, TaskStatus::Next);
+
+        let undone = db.undo().unwrap().unwrap();
+        assert_eq!(undone.operation, JournalOperation::UpdateTaskStatus);
+        assert_eq!(undone.task_title.as_deref(), Some("Next up"));
+        assert_eq!(db.get_task(&current.id).unwrap().status, TaskStatus::Now);
+        assert_eq!(db.get_task(&next.id).unwrap().status, TaskStatus::Next);
+
+        db.redo().unwrap().unwrap();
+        assert_eq!(db.get_task(&current.id).unwrap().status, TaskStatus::Next);
+        assert_eq!(db.get_task(&next.id).unwrap().status, TaskStatus::Now);
+        assert!(db.redo().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_undo_create_and_delete() {
+        let (_dir, db) = setup_test_db();
+        let task = create(&db, "Oops", TaskStatus::Next);
+        let child = db
+            .journaled(JournalOperation::CreateTask, None, |db| {
+                db.insert_task(&NewTask {
+                    title: "Child".to_string(),
+                    source: "test".to_string(),
+                    parent_id: Some(task.id.clone()),
+                    ..Default::default()
+                })
+            })
+            .unwrap();
+
+        db.journaled(JournalOperation::DeleteTask, Some(&task.id), |db| {
+            db.delete_task(&task.id)
+        })
+        .unwrap();
+        assert!(db.get_task(&child.id).is_err());
+
+        // Undoing the delete brings the subtask back too, content intact
+        db.undo().unwrap();
+        let restored = db.get_task(&task.id).unwrap();
+        assert_eq!(restored.title, task.title);
+        assert_eq!(restored.created_at, task.created_at);
+        assert!(restored.sync_version > task.sync_version);
+        assert!(db.get_task(&child.id).is_ok());
+
+        // Undoing the creates removes the tasks outright
+        db.undo().unwrap();
+        assert!(db.get_task(&child.id).is_err());
+        let undone = db.undo().unwrap().unwrap();
+        assert_eq!(undone.operation, JournalOperation::CreateTask);
+        assert_eq!(undone.task_id.as_deref(), Some(task.id.as_str()));
+        assert!(db.get_task(&task.id).is_err());
+        assert!(db.get_trash().unwrap().is_empty());
+        assert!(db.undo().unwrap().is_none());
+
+        let state = db.undo_state().unwrap();
+        assert!(state.undo.is_none());
+        assert_eq!(state.redo.unwrap().operation, JournalOperation::CreateTask);
+
+        // Redo replays all three, oldest first
+        for _ in 0..3 {
+            db.redo().unwrap().unwrap();
+        }
+        assert!(db.get_task(&task.id).is_err());
+        assert_eq!(db.get_trash().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_undo_recurring_completion_removes_next_occurrence() {
+        let (_dir, db) = setup_test_db();
+        let task = create(&db, "Water plants", TaskStatus::Next);
+        db.update_task(
+            &task.id,
+            TaskPatch {
+                recurrence: Some(Some(crate::db::Recurrence::Daily { interval: 1 })),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        set_status(&db, &task.id, TaskStatus::Done);
+        assert_eq!(db.get_all_tasks().unwrap().len(), 2);
+
+        db.undo().unwrap();
+        let tasks = db.get_all_tasks().unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].status, TaskStatus::Next);
+        assert!(tasks[0].recurrence.is_some());
+    }
+
+    #[test]
+    fn test_new_mutation_clears_redo_and_edits_block_undo() {
+        let (_dir, db) = setup_test_db();
+        create(&db, "First", TaskStatus::Next);
+        db.undo().unwrap();
+        assert!(db.undo_state().unwrap().redo.is_some());
+
+        let task = create(&db, "Second", TaskStatus::Next);
+        assert!(db.undo_state().unwrap().redo.is_none());
+
+        set_status(&db, &task.id, TaskStatus::Waiting);
+        let state = db.undo_state().unwrap();
+        assert_eq!(
+            state.undo.unwrap().operation,
+            JournalOperation::UpdateTaskStatus
+        );
+
+        // An edit made outside the journal isn't silently overwritten
+        db.update_task(
+            &task.id,
+            TaskPatch {
+                title: Some("Edited".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(db.undo().is_err());
+        assert_eq!(db.get_task(&task.id).unwrap().title, "Edited");
+        assert_eq!(db.get_task(&task.id).unwrap().status, TaskStatus::Waiting);
+    }
+
+    #[test]
+    fn test_journal_persists_across_restarts() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+
+        let db = Database::new(db_path.clone()).unwrap();
+        let task = create(&db, "Survivor", TaskStatus::Next);
+        set_status(&db, &task.id, TaskStatus::Done);
+        drop(db);
+
+        let db = Database::new(db_path).unwrap();
+        db.undo().unwrap().unwrap();
+        assert_eq!(db.get_task(&task.id).unwrap().status, TaskStatus::Next);
+    }
+}
@@ -0,0 +1,386 @@
+use anyhow::{anyhow, Result};
+use rusqlite::params_from_iter;
+use rusqlite::types::Value;
+use serde::{Deserialize, Serialize};
+
+use super::connection::Database;
+use super::models::{SortDirection, TaskFilter, TaskPage, TaskQuery, TaskSortField};
+use super::queries::TASK_COLUMNS;
+use super::tags::normalize_tag;
+
+const DEFAULT_PAGE_SIZE: u32 = 50;
+const MAX_PAGE_SIZE: u32 = 500;
+
+/// Position of the last row on a page: its sort key and id as a tie-breaker
+#[derive(Serialize, Deserialize)]
+struct Cursor {
+    key: serde_json::Value,
+    id: String,
+}
+
+impl Database {
+    /// List tasks matching a filter with keyset (cursor) pagination
+    pub fn list_tasks(&self, query: &TaskQuery) -> Result<TaskPage> {
+        let limit = query
+            .limit
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE);
+        let sort_key = sort_expression(query.sort, query.direction);
+        let (cmp, order) = match query.direction {
+            SortDirection::Asc => (">", "ASC"),
+            SortDirection::Desc => ("<", "DESC"),
+        };
+
+        let (mut clauses, mut values) = filter_clauses(&query.filter)?;
+
+        // Tasks without a context can be done anywhere, so they stay in
+        if query.filter.in_current_context {
+            if let Some(context) = self.current_context()? {
+                clauses.push("(context = ? COLLATE NOCASE OR context IS NULL)".to_string());
+                values.push(Value::Text(context));
+            }
+        }
+
+        if let Some(cursor) = &query.cursor {
+            let cursor: Cursor =
+                serde_json::from_str(cursor).map_err(|_| anyhow!("Invalid cursor"))?;
+            let key = match cursor.key {
+                serde_json::Value::Number(n) => {
+                    Value::Integer(n.as_i64().ok_or_else(|| anyhow!("Invalid cursor"))?)
+                }
+                serde_json::Value::String(s) => Value::Text(s),
+                _ => return Err(anyhow!("Invalid cursor")),
+            };
+
+            clauses.push(format!(
+                "({0} {1} ? OR ({0} = ? AND id {1} ?))",
+                sort_key, cmp
+            ));
+            values.push(key.clone());
+            values.push(key);
+            values.push(Value::Text(cursor.id));
+        }
+
+        let where_clause = if clauses.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", clauses.join(" AND "))
+        };
+
+        let sql = format!(
+            "SELECT {}, {} AS sort_key FROM tasks {} ORDER BY sort_key {}, id {} LIMIT {}",
+            TASK_COLUMNS,
+            sort_key,
+            where_clause,
+            order,
+            order,
+            limit + 1
+        );
+
+        let mut stmt = self.conn().prepare(&sql)?;
+        let mut rows = stmt
+            .query_map(params_from_iter(values), |row| {
+                Ok((self.row_to_task(row)?, row.get::<_, Value>("sort_key")?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let next_cursor = if rows.len() > limit as usize {
+            rows.truncate(limit as usize);
+            let (task, key) = rows.last().expect("page is non-empty");
+            let key = match key {
+                Value::Integer(i) => serde_json::Value::from(*i),
+                Value::Text(s) => serde_json::Value::from(s.clone()),
+                _ => serde_json::Value::Null,
+            };
+            Some(serde_json::to_string(&Cursor {
+                key,
+                id: task.id.clone(),
+            })?)
+        } else {
+            None
+        };
+
+        Ok(TaskPage {
+            tasks: rows.into_iter().map(|(task, _)| task).collect(),
+            next_cursor,
+        })
+    }
+}
+
+/// SQL expression used for ordering. Nullable columns are coalesced so that
+/// tasks without a value always sort last, whichever direction is used.
+fn sort_expression(sort: TaskSortField, direction: SortDirection) -> String {
+    let nulls_last = match direction {
+        SortDirection::Asc => i64::MAX,
+        SortDirection::Desc => i64::MIN,
+    };
+
+    match sort {
+        TaskSortField::CreatedAt => "created_at".to_string(),
+        TaskSortField::UpdatedAt => "updated_at".to_string(),
+        TaskSortField::ScheduledFor => format!("COALESCE(scheduled_for, {})", nulls_last),
+        TaskSortField::CompletedAt => format!("COALESCE(completed_at, {})", nulls_last),
+        TaskSortField::Title => "LOWER(title)".to_string(),
+        TaskSortField::Priority => format!("COALESCE(priority, {})", nulls_last),
+    }
+}
+
+/// Build the WHERE conditions and their bound values for a filter.
+/// Tasks in the trash never match.
+pub(crate) fn filter_clauses(filter: &TaskFilter) -> Result<(Vec<String>, Vec<Value>)> {
+    let mut clauses = vec!["deleted_at IS NULL".to_string()];
+    let mut values = Vec::new();
+
+    if !filter.statuses.is_empty() {
+        let placeholders = vec!["?"; filter.statuses.len()].join(", ");
+        clauses.push(format!("status IN ({})", placeholders));
+        values.extend(
+            filter
+                .statuses
+                .iter()
+                .map(|s| Value::Text(s.as_str().to_string())),
+        );
+    }
+
+    if let Some(context) = &filter.context {
+        clauses.push("context = ? COLLATE NOCASE".to_string());
+        values.push(Value::Text(
+            context.trim().trim_start_matches('@').to_string(),
+        ));
+    }
+
+    if let Some(tag) = &filter.tag {
+        clauses.push(
+            "id IN (SELECT task_tags.task_id FROM task_tags
+                    JOIN tags ON tags.id = task_tags.tag_id WHERE tags.name = ?)"
+                .to_string(),
+        );
+        values.push(Value::Text(normalize_tag(tag)?));
+    }
+
+    let ranges = [
+        ("scheduled_for", ">=", filter.scheduled_from),
+        ("scheduled_for", "<", filter.scheduled_to),
+        ("completed_at", ">=", filter.completed_from),
+        ("completed_at", "<", filter.completed_to),
+    ];
+    for (column, op, bound) in ranges {
+        if let Some(bound) = bound {
+            clauses.push(format!("{} {} ?", column, op));
+            values.push(Value::Integer(bound));
+        }
+    }
+
+    if let Some(text) = filter
+        .text
+        .as_deref()
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+    {
+        let pattern = format!(
+            "%{}%",
+            text.replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+        );
+        clauses.push(
+            "(title LIKE ? ESCAPE '\\' OR COALESCE(original_input, '') LIKE ? ESCAPE '\\')"
+                .to_string(),
+        );
+        values.push(Value::Text(pattern.clone()));
+        values.push(Value::Text(pattern));
+    }
+
+    Ok((clauses, values))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{Task, TaskStatus};
+    use tempfile::{tempdir, TempDir};
+
+    fn setup_test_db() -> (TempDir, Database) {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let db = Database::new(db_path).unwrap();
+        (dir, db)
+    }
+
+    fn create(
+        db: &Database,
+        title: &str,
+        status: TaskStatus,
+        context: Option<&str>,
+        scheduled_for: Option<i64>,
+    ) -> Task {
+        db.create_task(
+            title.to_string(),
+            status,
+            context.map(|c| c.to_string()),
+            scheduled_for,
+            Some(format!("{} (captured)", title)),
+            "test".to_string(),
+            Vec::new(),
+        )
+        .unwrap()
+    }
+
+    fn titles(page: &TaskPage) -> Vec<&str> {
+        page.tasks.iter().map(|t| t.title.as_str()).collect()
+    }
+
+    #[test]
+    fn test_list_tasks_filters() {
+        let (_dir, db) = setup_test_db();
+
+        create(
+            &db,
+            "Buy milk",
+            TaskStatus::Next,
+            Some("errands"),
+            Some(100),
+        );
+        create(
+            &db,
+            "Write 100% report",
+            TaskStatus::Waiting,
+            Some("office"),
+            Some(200),
+        );
+        create(&db, "Old idea", TaskStatus::Someday, None, None);
+        let done = create(&db, "Filed taxes", TaskStatus::Next, None, None);
+        db.update_task_status(&done.id, TaskStatus::Done).unwrap();
+
+        let everything = db.list_tasks(&TaskQuery::default()).unwrap();
+        assert_eq!(everything.tasks.len(), 4);
+        assert!(everything.next_cursor.is_none());
+
+        let query: TaskQuery = serde_json::from_str(
+            r#"{"statuses": ["someday", "done"], "sort": "title", "direction": "asc"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            titles(&db.list_tasks(&query).unwrap()),
+            vec!["Filed taxes", "Old idea"]
+        );
+
+        let query = TaskQuery {
+            filter: TaskFilter {
+                // Contexts match regardless of case, as in context mode
+                context: Some("@Office".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            titles(&db.list_tasks(&query).unwrap()),
+            vec!["Write 100% report"]
+        );
+
+        let query = TaskQuery {
+            filter: TaskFilter {
+                scheduled_from: Some(150),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            titles(&db.list_tasks(&query).unwrap()),
+            vec!["Write 100% report"]
+        );
+
+        // LIKE wildcards in the search text are matched literally
+        let query = TaskQuery {
+            filter: TaskFilter {
+                text: Some("0%".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            titles(&db.list_tasks(&query).unwrap()),
+            vec!["Write 100% report"]
+        );
+
+        let query = TaskQuery {
+            filter: TaskFilter {
+                text: Some("CAPTURED".to_string()),
+                completed_from: Some(0),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(titles(&db.list_tasks(&query).unwrap()), vec!["Filed taxes"]);
+    }
+
+    #[test]
+    fn test_list_tasks_by_tag() {
+        let (_dir, db) = setup_test_db();
+
+        let task = create(&db, "Tagged", TaskStatus::Next, None, None);
+        create(&db, "Untagged", TaskStatus::Next, None, None);
+        db.add_task_tag(&task.id, "work").unwrap();
+
+        let query = TaskQuery {
+            filter: TaskFilter {
+                tag: Some("#work".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(titles(&db.list_tasks(&query).unwrap()), vec!["Tagged"]);
+    }
+
+    #[test]
+    fn test_list_tasks_cursor_pagination() {
+        let (_dir, db) = setup_test_db();
+
+        // Several tasks share a scheduled_for value and two have none,
+        // so paging has to fall back on the id tie-breaker and null handling
+        for i in 0..7 {
+            let scheduled = if i < 5 {
+                Some(1_000 + (i % 2) * 100)
+            } else {
+                None
+            };
+            create(
+                &db,
+                &format!("Task {}", i),
+                TaskStatus::Next,
+                None,
+                scheduled,
+            );
+        }
+
+        let mut query = TaskQuery {
+            sort: TaskSortField::ScheduledFor,
+            direction: SortDirection::Asc,
+            limit: Some(3),
+            ..Default::default()
+        };
+
+        let mut seen = Vec::new();
+        loop {
+            let page = db.list_tasks(&query).unwrap();
+            seen.extend(page.tasks.iter().map(|t| (t.scheduled_for, t.id.clone())));
+            match page.next_cursor {
+                Some(cursor) => query.cursor = Some(cursor),
+                None => break,
+            }
+        }
+
+        assert_eq!(seen.len(), 7);
+        let mut ids: Vec<_> = seen.iter().map(|(_, id)| id.clone()).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), 7);
+
+        let scheduled: Vec<_> = seen.iter().map(|(s, _)| *s).collect();
+        assert_eq!(&scheduled[..3], &[Some(1_000); 3]);
+        assert_eq!(&scheduled[5..], &[None, None]);
+
+        query.cursor = Some("garbage".to_string());
+        assert!(db.list_tasks(&query).is_err());
+    }
+}
@@ -0,0 +1,113 @@
+use anyhow::Result;
+
+use super::connection::Database;
+use super::models::TaskStatus;
+use super::todotxt::format_date;
+
+/// Sections in the order they appear in the export
+const SECTIONS: [(TaskStatus, &str); 5] = [
+    (TaskStatus::Now, "Now"),
+    (TaskStatus::Next, "Next"),
+    (TaskStatus::Waiting, "Waiting"),
+    (TaskStatus::Someday, "Someday"),
+    (TaskStatus::Done, "Done"),
+];
+
+impl Database {
+    /// Every task outside the trash as a Markdown checklist, one section per
+    /// status. Empty sections are left out.
+    pub fn export_markdown(&self) -> Result<String> {
+        let tz = self.timezone()?;
+        let projects = self.project_titles()?;
+        let tasks = self.live_tasks()?;
+
+        let mut out = String::from("# Tasks\n");
+        for (status, heading) in SECTIONS {
+            let section: Vec<_> = tasks.iter().filter(|task| task.status == status).collect();
+            if section.is_empty() {
+                continue;
+            }
+
+            out.push_str(&format!("\n## {}\n\n", heading));
+            for task in section {
+                let mut details = Vec::new();
+                if let Some(title) = task.project_id.as_ref().and_then(|id| projects.get(id)) {
+                    details.push(format!("project: {}", title));
+                }
+                if let Some(context) = &task.context {
+                    details.push(format!("@{}", context));
+                }
+                if let Some(waiting_on) = &task.waiting_on {
+                    details.push(format!("waiting on {}", waiting_on));
+                }
+                if let Some(scheduled_for) = task.scheduled_for {
+                    details.push(format!("due {}", format_date(scheduled_for, &tz)));
+                }
+                if let Some(completed_at) = task.completed_at {
+                    details.push(format!("done {}", format_date(completed_at, &tz)));
+                }
+
+                let checkbox = if status == TaskStatus::Done { "x" } else { " " };
+                out.push_str(&format!(
+                    "- [{}] {}",
+                    checkbox,
+                    task.title.replace('\n', " ")
+                ));
+                if !details.is_empty() {
+                    out.push_str(&format!(" ({})", details.join(", ")));
+                }
+                for tag in &task.tags {
+                    out.push_str(&format!(" #{}", tag));
+                }
+                out.push('\n');
+            }
+        }
+
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::NewTask;
+    use tempfile::{tempdir, TempDir};
+
+    fn setup_test_db() -> (TempDir, Database) {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let db = Database::new(db_path).unwrap();
+        db.set_timezone("UTC").unwrap();
+        (dir, db)
+    }
+
+    #[test]
+    fn test_export_markdown() {
+        let (_dir, db) = setup_test_db();
+        db.insert_task(&NewTask {
+            title: "Write report".to_string(),
+            status: TaskStatus::Next,
+            context: Some("work".to_string()),
+            scheduled_for: Some(1_704_099_600),
+            source: "test".to_string(),
+            tags: vec!["q1".to_string()],
+            ..Default::default()
+        })
+        .unwrap();
+        let done = db
+            .insert_task(&NewTask {
+                title: "Book flights".to_string(),
+                status: TaskStatus::Next,
+                source: "test".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        db.update_task_status(&done.id, TaskStatus::Done).unwrap();
+
+        let markdown = db.export_markdown().unwrap();
+        assert!(markdown.starts_with("# Tasks\n\n## Next\n\n"));
+        assert!(markdown.contains("- [ ] Write report (@work, due 2024-01-01) #q1\n"));
+        assert!(markdown.contains("\n## Done\n\n- [x] Book flights (done "));
+        assert!(!markdown.contains("## Waiting"));
+    }
+}
@@ -0,0 +1,126 @@
+use anyhow::{anyhow, bail, Result};
+use sha2::{Digest, Sha256};
+
+include!(concat!(env!("OUT_DIR"), "/migrations.rs"));
+
+/// A numbered schema migration embedded from `migrations/`.
+///
+/// Files are named `NNN_description.sql`, with an optional
+/// `NNN_description.down.sql` that reverts it.
+#[derive(Debug, Clone)]
+pub struct Migration {
+    pub version: i32,
+    pub name: &'static str,
+    pub up: &'static str,
+    pub down: Option<&'static str>,
+}
+
+impl Migration {
+    /// SHA-256 of the up script, used to detect edits to applied migrations
+    pub fn checksum(&self) -> String {
+        Sha256::digest(self.up.as_bytes())
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+}
+
+/// All embedded migrations, in version order
+pub fn all() -> Result<Vec<Migration>> {
+    parse(EMBEDDED_MIGRATIONS)
+}
+
+/// The newest schema version this build knows about
+pub fn latest_version() -> i32 {
+    all()
+        .ok()
+        .and_then(|migrations| migrations.last().map(|m| m.version))
+        .unwrap_or(0)
+}
+
+/// Pair up/down scripts by file name and check versions run 1, 2, 3, ...
+fn parse(files: &[(&'static str, &'static str)]) -> Result<Vec<Migration>> {
+    let mut migrations: Vec<Migration> = Vec::new();
+    let mut downs: Vec<(&'static str, &'static str)> = Vec::new();
+
+    for &(file_name, sql) in files {
+        if let Some(name) = file_name.strip_suffix(".down.sql") {
+            downs.push((name, sql));
+        } else if let Some(name) = file_name.strip_suffix(".sql") {
+            let version = name
+                .split('_')
+                .next()
+                .and_then(|v| v.parse().ok())
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Migration {} does not start with a version number",
+                        file_name
+                    )
+                })?;
+            migrations.push(Migration {
+                version,
+                name: file_name,
+                up: sql,
+                down: None,
+            });
+        }
+    }
+
+    for (name, sql) in downs {
+        let migration = migrations
+            .iter_mut()
+            .find(|m| m.name.strip_suffix(".sql") == Some(name))
+            .ok_or_else(|| {
+                anyhow!(
+                    "Down migration {}.down.sql has no matching up migration",
+                    name
+                )
+            })?;
+        migration.down = Some(sql);
+    }
+
+    migrations.sort_by_key(|m| m.version);
+
+    for (expected, migration) in (1..).zip(&migrations) {
+        if migration.version != expected {
+            bail!(
+                "Migration versions must be contiguous: expected {}, found {}",
+                expected,
+                migration.name
+            );
+        }
+    }
+
+    Ok(migrations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embedded_migrations_are_valid() {
+        let migrations = all().unwrap();
+
+        assert_eq!(migrations[0].name, "001_initial_schema.sql");
+        assert_eq!(latest_version(), migrations.len() as i32);
+        assert!(migrations.iter().all(|m| m.down.is_some()));
+    }
+
+    #[test]
+    fn test_parse_rejects_gaps_and_orphans() {
+        assert!(parse(&[("001_a.sql", ""), ("003_c.sql", "")]).is_err());
+        assert!(parse(&[("001_a.sql", ""), ("002_b.down.sql", "")]).is_err());
+        assert!(parse(&[("initial.sql", "")]).is_err());
+
+        let parsed = parse(&[
+            ("002_b.sql", "B"),
+            ("001_a.down.sql", "-A"),
+            ("001_a.sql", "A"),
+        ])
+        .unwrap();
+        assert_eq!(parsed[0].down, Some("-A"));
+        assert_eq!(parsed[1].version, 2);
+        assert_ne!(parsed[0].checksum(), parsed[1].checksum());
+    }
+}
@@ -1,6 +1,25 @@
+pub mod backup;
 pub mod connection;
+pub mod contexts;
+pub mod history;
+pub mod journal;
+pub mod list;
+pub mod markdown;
+pub mod migrations;
 pub mod models;
+pub mod projects;
 pub mod queries;
+pub mod recurrence;
+pub mod review;
+pub mod search;
+pub mod stats;
+pub mod suggest;
+pub mod sync;
+pub mod tags;
+pub mod todotxt;
+pub mod trash;
+pub mod waiting;
 
 pub use connection::Database;
 pub use models::*;
+pub use recurrence::Recurrence;
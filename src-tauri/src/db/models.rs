@@ -1,6 +1,8 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use super::recurrence::Recurrence;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Task {
     pub id: String,
     pub title: String,
@@ -12,14 +14,61 @@ pub struct Task {
     pub updated_at: i64,
     pub original_input: Option<String>,
     pub source: String,
-    pub tags: Option<String>,
+    pub tags: Vec<String>,
     pub sync_version: i32,
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
+    /// Person or thing a WAITING task is waiting on
+    #[serde(default)]
+    pub waiting_on: Option<String>,
+    /// When the task last became WAITING
+    #[serde(default)]
+    pub waiting_since: Option<i64>,
+    /// When to chase the person we are waiting on
+    #[serde(default)]
+    pub follow_up_at: Option<i64>,
+    #[serde(default)]
+    pub priority: Option<Priority>,
+    /// Estimated effort
+    #[serde(default)]
+    pub effort_minutes: Option<i32>,
+    #[serde(default)]
+    pub energy: Option<Energy>,
+    #[serde(default)]
+    pub project_id: Option<String>,
+    /// Task this one is a subtask of
+    #[serde(default)]
+    pub parent_id: Option<String>,
+    /// When the task was moved to the trash
+    #[serde(default)]
+    pub deleted_at: Option<i64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+/// Fields for creating a task
+#[derive(Debug, Clone, Default)]
+pub struct NewTask {
+    pub title: String,
+    pub status: TaskStatus,
+    pub context: Option<String>,
+    pub scheduled_for: Option<i64>,
+    pub original_input: Option<String>,
+    pub source: String,
+    pub tags: Vec<String>,
+    pub recurrence: Option<Recurrence>,
+    pub waiting_on: Option<String>,
+    pub priority: Option<Priority>,
+    pub effort_minutes: Option<i32>,
+    pub energy: Option<Energy>,
+    pub project_id: Option<String>,
+    /// Subtasks always belong to their parent's project
+    pub parent_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum TaskStatus {
     Now,
+    #[default]
     Next,
     Waiting,
     Someday,
@@ -49,15 +98,527 @@ impl TaskStatus {
     }
 }
 
+/// Task priority, stored as 1 (high) to 3 (low)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    High = 1,
+    Medium = 2,
+    Low = 3,
+}
+
+impl Priority {
+    pub fn as_i32(self) -> i32 {
+        self as i32
+    }
+
+    pub fn from_i32(value: i32) -> Option<Self> {
+        match value {
+            1 => Some(Priority::High),
+            2 => Some(Priority::Medium),
+            3 => Some(Priority::Low),
+            _ => None,
+        }
+    }
+}
+
+/// How much energy a task takes
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Energy {
+    Low,
+    Medium,
+    High,
+}
+
+impl Energy {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Energy::Low => "low",
+            Energy::Medium => "medium",
+            Energy::High => "high",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "low" => Some(Energy::Low),
+            "medium" => Some(Energy::Medium),
+            "high" => Some(Energy::High),
+            _ => None,
+        }
+    }
+}
+
+/// Partial update of a task's editable fields.
+///
+/// A missing field is left unchanged. For nullable fields, an explicit
+/// `null` clears the value.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TaskPatch {
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub status: Option<TaskStatus>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub context: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub scheduled_for: Option<Option<i64>>,
+    /// Replaces the full set of tags
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub recurrence: Option<Option<Recurrence>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub waiting_on: Option<Option<String>>,
+    /// Only WAITING tasks have a follow-up date
+    #[serde(default, deserialize_with = "nullable")]
+    pub follow_up_at: Option<Option<i64>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub priority: Option<Option<Priority>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub effort_minutes: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub energy: Option<Option<Energy>>,
+    /// Moves the task and its subtasks; not allowed on a subtask
+    #[serde(default, deserialize_with = "nullable")]
+    pub project_id: Option<Option<String>>,
+    /// Makes the task a subtask, moving it into the parent's project
+    #[serde(default, deserialize_with = "nullable")]
+    pub parent_id: Option<Option<String>>,
+}
+
+impl TaskPatch {
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none()
+            && self.status.is_none()
+            && self.context.is_none()
+            && self.scheduled_for.is_none()
+            && self.tags.is_none()
+            && self.recurrence.is_none()
+            && self.waiting_on.is_none()
+            && self.follow_up_at.is_none()
+            && self.priority.is_none()
+            && self.effort_minutes.is_none()
+            && self.energy.is_none()
+            && self.project_id.is_none()
+            && self.parent_id.is_none()
+    }
+}
+
+/// Distinguish a field explicitly set to `null` from one that is absent
+fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Filters for listing tasks. Every filter is optional and they combine with AND.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct TaskFilter {
+    /// Match any of these statuses (empty = all)
+    pub statuses: Vec<TaskStatus>,
+    pub context: Option<String>,
+    pub tag: Option<String>,
+    pub scheduled_from: Option<i64>,
+    pub scheduled_to: Option<i64>,
+    pub completed_from: Option<i64>,
+    pub completed_to: Option<i64>,
+    /// Case-insensitive substring match on title and original input
+    pub text: Option<String>,
+    /// Limit to the current context, if one is set (context-free tasks included)
+    pub in_current_context: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskSortField {
+    #[default]
+    CreatedAt,
+    UpdatedAt,
+    ScheduledFor,
+    CompletedAt,
+    Title,
+    /// High priority first when ascending
+    Priority,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortDirection {
+    Asc,
+    #[default]
+    Desc,
+}
+
+/// A filtered, sorted, paginated task listing request
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct TaskQuery {
+    #[serde(flatten)]
+    pub filter: TaskFilter,
+    pub sort: TaskSortField,
+    pub direction: SortDirection,
+    /// Opaque cursor returned as `next_cursor` by the previous page
+    pub cursor: Option<String>,
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TaskPage {
+    pub tasks: Vec<Task>,
+    pub next_cursor: Option<String>,
+}
+
+/// A full-text search hit. Snippets are HTML-escaped with matched terms
+/// wrapped in `<mark>`/`</mark>`; `input_snippet` is only set when the
+/// original input itself matched.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchResult {
+    pub task: Task,
+    pub title_snippet: String,
+    pub input_snippet: Option<String>,
+    pub rank: f64,
+}
+
+/// A tag and how many tasks carry it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TagCount {
+    pub name: String,
+    pub task_count: i64,
+    pub open_task_count: i64,
+}
+
+/// A context and how many tasks are marked with it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ContextCount {
+    pub name: String,
+    pub task_count: i64,
+    pub open_task_count: i64,
+}
+
+/// A WAITING task with its follow-up state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WaitingTask {
+    #[serde(flatten)]
+    pub task: Task,
+    /// The follow-up date has passed
+    pub follow_up_overdue: bool,
+    pub days_waiting: i64,
+    pub last_follow_up_at: Option<i64>,
+}
+
+/// What contributed to a suggestion's score
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScoreFactor {
+    Schedule,
+    Age,
+    Context,
+    Priority,
+    Effort,
+}
+
+/// One factor's contribution to a suggestion, with a human-readable reason
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ScoreReason {
+    pub factor: ScoreFactor,
+    pub points: f64,
+    pub reason: String,
+}
+
+/// A NEXT task ranked as a candidate for NOW
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Suggestion {
+    pub task: Task,
+    pub score: f64,
+    pub reasons: Vec<ScoreReason>,
+}
+
+/// A logged follow-up nudge on a WAITING task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FollowUp {
+    pub id: String,
+    pub task_id: String,
+    pub nudged_at: i64,
+    pub note: Option<String>,
+}
+
+/// A task mutation that can be undone
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalOperation {
+    CreateTask,
+    UpdateTaskStatus,
+    DeleteTask,
+}
+
+impl JournalOperation {
+    pub fn as_str(&self) -> &str {
+        match self {
+            JournalOperation::CreateTask => "create_task",
+            JournalOperation::UpdateTaskStatus => "update_task_status",
+            JournalOperation::DeleteTask => "delete_task",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "create_task" => Some(JournalOperation::CreateTask),
+            "update_task_status" => Some(JournalOperation::UpdateTaskStatus),
+            "delete_task" => Some(JournalOperation::DeleteTask),
+            _ => None,
+        }
+    }
+}
+
+/// One recorded change to a task. `field` is `created`, a task field name or
+/// `tags`; values are stored as text, with recurrence as its RRULE.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TaskEvent {
+    pub id: i64,
+    pub task_id: String,
+    pub field: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    pub created_at: i64,
+    /// What made the change: `app`, `sync`, `undo`, `redo`, `review`,
+    /// `recurrence` or `restore`
+    pub source: String,
+}
+
+/// Tasks completed in one period, keyed by its first day (`YYYY-MM-DD`)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PeriodCount {
+    pub start: String,
+    pub completed: i64,
+}
+
+/// Time tasks spent in a status, summed over every task
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StatusTime {
+    pub status: TaskStatus,
+    pub seconds: i64,
+}
+
+/// Of the tasks captured in a range for one context or source, how many are done
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CompletionRate {
+    /// `None` for tasks without a context
+    pub name: Option<String>,
+    pub created: i64,
+    pub completed: i64,
+    pub rate: f64,
+}
+
+/// Productivity figures for `from..to`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Stats {
+    pub from: i64,
+    pub to: i64,
+    pub completed_per_day: Vec<PeriodCount>,
+    /// Weeks start on Monday
+    pub completed_per_week: Vec<PeriodCount>,
+    /// Median capture-to-done time of the tasks completed in the range
+    pub median_lead_time_seconds: Option<i64>,
+    pub time_in_status: Vec<StatusTime>,
+    pub completion_by_context: Vec<CompletionRate>,
+    pub completion_by_source: Vec<CompletionRate>,
+}
+
+/// A task read from a todo.txt line, before it is created
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct TodoTxtTask {
+    /// 1-based line number in the imported text
+    pub line: usize,
+    pub original: String,
+    pub title: String,
+    pub status: TaskStatus,
+    pub priority: Option<Priority>,
+    pub context: Option<String>,
+    /// The `+project` word, matched to a project title with spaces as dashes
+    pub project: Option<String>,
+    pub tags: Vec<String>,
+    pub scheduled_for: Option<i64>,
+    pub created_at: Option<i64>,
+    pub completed_at: Option<i64>,
+}
+
+/// A todo.txt line that could not be imported
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SkippedLine {
+    pub line: usize,
+    pub text: String,
+    pub reason: String,
+}
+
+/// What a todo.txt import created, or would create on a dry run
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub tasks: Vec<TodoTxtTask>,
+    /// Projects no existing one matched
+    pub new_projects: Vec<String>,
+    pub skipped: Vec<SkippedLine>,
+}
+
+/// A settings row as stored
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Setting {
+    pub key: String,
+    pub value: String,
+    pub updated_at: i64,
+}
+
+/// A JSON snapshot of the database. Projects come along so the tasks in
+/// them can be restored; follow-ups and task history so they aren't lost.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Backup {
+    pub format_version: u32,
+    /// Migration version of the database the backup was taken from
+    pub schema_version: i32,
+    pub exported_at: i64,
+    pub settings: Vec<Setting>,
+    #[serde(default)]
+    pub projects: Vec<Project>,
+    pub tasks: Vec<Task>,
+    pub review_log: Vec<ReviewLog>,
+    #[serde(default)]
+    pub review_triage: Vec<ReviewTriage>,
+    pub sync_log: Vec<SyncLog>,
+    #[serde(default)]
+    pub follow_ups: Vec<FollowUp>,
+    #[serde(default)]
+    pub task_events: Vec<TaskEvent>,
+}
+
+/// How a backup is restored
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RestoreMode {
+    /// Add what's missing and take records the backup has newer versions of
+    Merge,
+    /// Wipe tasks, projects, settings and logs and load the backup as is
+    Replace,
+}
+
+/// How many records a restore wrote
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct RestoreSummary {
+    pub settings: usize,
+    pub projects: usize,
+    pub tasks: usize,
+    pub review_log: usize,
+    pub review_triage: usize,
+    pub sync_log: usize,
+    pub follow_ups: usize,
+    pub task_events: usize,
+}
+
+/// A journaled mutation, as offered for undo or redo
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JournalEntry {
+    pub id: i64,
+    pub operation: JournalOperation,
+    /// The task the mutation was aimed at
+    pub task_id: Option<String>,
+    pub task_title: Option<String>,
+    pub created_at: i64,
+}
+
+/// What undo and redo would do next
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UndoState {
+    pub undo: Option<JournalEntry>,
+    pub redo: Option<JournalEntry>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectStatus {
+    #[default]
+    Active,
+    Someday,
+    Done,
+}
+
+impl ProjectStatus {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ProjectStatus::Active => "active",
+            ProjectStatus::Someday => "someday",
+            ProjectStatus::Done => "done",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "active" => Some(ProjectStatus::Active),
+            "someday" => Some(ProjectStatus::Someday),
+            "done" => Some(ProjectStatus::Done),
+            _ => None,
+        }
+    }
+}
+
+/// A multi-step outcome grouping tasks
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Project {
+    pub id: String,
+    pub title: String,
+    pub status: ProjectStatus,
+    pub notes: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub completed_at: Option<i64>,
+    pub sync_version: i32,
+}
+
+/// Partial update of a project; an explicit `null` clears the notes
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ProjectPatch {
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub status: Option<ProjectStatus>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub notes: Option<Option<String>>,
+}
+
+/// How many of a set of tasks are done
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct Progress {
+    pub total: i64,
+    pub done: i64,
+}
+
+/// A project with its roll-up and what to do next
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectSummary {
+    #[serde(flatten)]
+    pub project: Project,
+    /// Over every task in the project, subtasks included
+    pub progress: Progress,
+    /// Oldest NOW or NEXT task in the project
+    pub next_action: Option<Task>,
+    /// Active but nothing is NOW or NEXT, so the project is stalled
+    pub needs_next_action: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DashboardData {
     pub now_task: Option<Task>,
     pub next_tasks: Vec<Task>,
-    pub waiting_tasks: Vec<Task>,
+    pub waiting_tasks: Vec<WaitingTask>,
     pub review_due_in_days: i32,
+    /// Context mode the lists above are filtered to, if any
+    pub current_context: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReviewLog {
     pub id: String,
     pub started_at: i64,
@@ -68,7 +629,41 @@ pub struct ReviewLog {
     pub tasks_deleted: i32,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// A task that has had its decision in a review
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReviewTriage {
+    pub review_id: String,
+    pub task_id: String,
+    pub triaged_at: i64,
+}
+
+/// Outcome of triaging a single task during a weekly review
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReviewDecision {
+    /// Leave the task as it is
+    Keep,
+    /// Mark the task done
+    Complete,
+    /// Push the task out to SOMEDAY
+    Defer,
+    /// Remove the task
+    Delete,
+}
+
+impl ReviewDecision {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "keep" => Some(ReviewDecision::Keep),
+            "complete" => Some(ReviewDecision::Complete),
+            "defer" => Some(ReviewDecision::Defer),
+            "delete" => Some(ReviewDecision::Delete),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SyncLog {
     pub id: String,
     pub timestamp: i64,
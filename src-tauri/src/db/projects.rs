@@ -0,0 +1,549 @@
+use anyhow::{anyhow, bail, Result};
+use chrono::Utc;
+use rusqlite::{params, OptionalExtension, Row};
+use uuid::Uuid;
+
+use super::connection::Database;
+use super::models::{Progress, Project, ProjectPatch, ProjectStatus, ProjectSummary, Task};
+use super::queries::TASK_COLUMNS;
+
+const PROJECT_COLUMNS: &str =
+    "id, title, status, notes, created_at, updated_at, completed_at, sync_version";
+
+impl Database {
+    /// Create an active project
+    pub fn create_project(&self, title: &str, notes: Option<&str>) -> Result<Project> {
+        let title = title.trim();
+        if title.is_empty() {
+            bail!("Project title cannot be empty");
+        }
+
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now().timestamp();
+        let notes = notes.map(str::trim).filter(|notes| !notes.is_empty());
+
+        self.conn().execute(
+            "INSERT INTO projects (id, title, status, notes, created_at, updated_at, sync_version)
+             VALUES (?1, ?2, 'active', ?3, ?4, ?4, 1)",
+            params![id, title, notes, now],
+        )?;
+
+        self.get_project(&id)
+    }
+
+    /// Get a project by ID
+    pub fn get_project(&self, id: &str) -> Result<Project> {
+        self.conn()
+            .query_row(
+                &format!("SELECT {} FROM projects WHERE id = ?1", PROJECT_COLUMNS),
+                params![id],
+                row_to_project,
+            )
+            .optional()?
+            .ok_or_else(|| anyhow!("Project {} not found", id))
+    }
+
+    /// Get every project in the database, oldest first
+    pub fn get_all_projects(&self) -> Result<Vec<Project>> {
+        let mut stmt = self.conn().prepare(&format!(
+            "SELECT {} FROM projects ORDER BY created_at ASC",
+            PROJECT_COLUMNS
+        ))?;
+
+        let projects = stmt
+            .query_map([], row_to_project)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(projects)
+    }
+
+    /// List projects with their progress, optionally only those in one status
+    pub fn get_projects(&self, status: Option<ProjectStatus>) -> Result<Vec<ProjectSummary>> {
+        self.get_all_projects()?
+            .into_iter()
+            .filter(|project| status.is_none_or(|status| project.status == status))
+            .map(|project| self.summarize_project(project))
+            .collect()
+    }
+
+    /// Get a project with its progress and next action
+    pub fn get_project_summary(&self, id: &str) -> Result<ProjectSummary> {
+        let project = self.get_project(id)?;
+        self.summarize_project(project)
+    }
+
+    /// Apply a partial update to a project
+    pub fn update_project(&self, id: &str, patch: ProjectPatch) -> Result<Project> {
+        let mut project = self.get_project(id)?;
+
+        if let Some(title) = patch.title {
+            let title = title.trim();
+            if title.is_empty() {
+                bail!("Project title cannot be empty");
+            }
+            project.title = title.to_string();
+        }
+
+        if let Some(notes) = patch.notes {
+            project.notes = notes
+                .map(|notes| notes.trim().to_string())
+                .filter(|notes| !notes.is_empty());
+        }
+
+        let now = Utc::now().timestamp();
+
+        if let Some(status) = patch.status {
+            if status == ProjectStatus::Done && project.status != ProjectStatus::Done {
+                project.completed_at = Some(now);
+            } else if status != ProjectStatus::Done {
+                project.completed_at = None;
+            }
+            project.status = status;
+        }
+
+        self.conn().execute(
+            "UPDATE projects SET title = ?1, status = ?2, notes = ?3, completed_at = ?4,
+                updated_at = ?5, sync_version = sync_version + 1
+             WHERE id = ?6",
+            params![
+                project.title,
+                project.status.as_str(),
+                project.notes,
+                project.completed_at,
+                now,
+                id,
+            ],
+        )?;
+
+        self.get_project(id)
+    }
+
+    /// Delete a project. Its tasks and their subtasks go to the trash; the
+    /// project's own tasks come back out of any project when restored.
+    pub fn delete_project(&self, id: &str) -> Result<()> {
+        let tx = self.conn().unchecked_transaction()?;
+        self.conn().execute(
+            "WITH RECURSIVE subtree(id) AS (
+                SELECT id FROM tasks WHERE project_id = ?1
+                UNION
+                SELECT tasks.id FROM tasks JOIN subtree ON tasks.parent_id = subtree.id
+             )
+             UPDATE tasks SET
+                deleted_at = COALESCE(deleted_at, ?2),
+                sync_version = sync_version + 1
+             WHERE id IN subtree",
+            params![id, Utc::now().timestamp()],
+        )?;
+        self.conn()
+            .execute("DELETE FROM projects WHERE id = ?1", params![id])?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Delete a project row without taking its tasks along: they stay, out of
+    /// any project, at their current sync_version. Used by the sync engine
+    /// when a project was deleted on another device; the tasks themselves
+    /// follow in the task merge.
+    pub fn remove_project(&self, id: &str) -> Result<()> {
+        self.conn()
+            .execute("DELETE FROM projects WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    /// Write a project exactly as given, including its timestamps and sync_version.
+    /// Used by the sync engine to apply remote changes.
+    pub fn upsert_project(&self, project: &Project) -> Result<()> {
+        self.conn().execute(
+            "INSERT INTO projects (id, title, status, notes, created_at, updated_at, completed_at, sync_version)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+             ON CONFLICT(id) DO UPDATE SET
+                title = excluded.title,
+                status = excluded.status,
+                notes = excluded.notes,
+                created_at = excluded.created_at,
+                updated_at = excluded.updated_at,
+                completed_at = excluded.completed_at,
+                sync_version = excluded.sync_version",
+            params![
+                project.id,
+                project.title,
+                project.status.as_str(),
+                project.notes,
+                project.created_at,
+                project.updated_at,
+                project.completed_at,
+                project.sync_version,
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// Every task in a project, subtasks included, oldest first
+    pub fn get_project_tasks(&self, project_id: &str) -> Result<Vec<Task>> {
+        let mut stmt = self.conn().prepare(&format!(
+            "SELECT {} FROM tasks
+             WHERE project_id = ?1 AND deleted_at IS NULL
+             ORDER BY created_at ASC",
+            TASK_COLUMNS
+        ))?;
+
+        let tasks = stmt
+            .query_map(params![project_id], |row| self.row_to_task(row))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(tasks)
+    }
+
+    /// Direct subtasks of a task, oldest first
+    pub fn get_subtasks(&self, task_id: &str) -> Result<Vec<Task>> {
+        let mut stmt = self.conn().prepare(&format!(
+            "SELECT {} FROM tasks
+             WHERE parent_id = ?1 AND deleted_at IS NULL
+             ORDER BY created_at ASC",
+            TASK_COLUMNS
+        ))?;
+
+        let tasks = stmt
+            .query_map(params![task_id], |row| self.row_to_task(row))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(tasks)
+    }
+
+    /// Progress over all of a task's subtasks, however deeply nested
+    pub fn get_task_progress(&self, task_id: &str) -> Result<Progress> {
+        self.get_task(task_id)?;
+
+        let progress = self.conn().query_row(
+            "WITH RECURSIVE subtree(id) AS (
+                SELECT id FROM tasks WHERE parent_id = ?1
+                UNION
+                SELECT tasks.id FROM tasks JOIN subtree ON tasks.parent_id = subtree.id
+             )
+             SELECT COUNT(*), COUNT(CASE WHEN status = 'done' THEN 1 END)
+             FROM tasks WHERE id IN subtree AND deleted_at IS NULL",
+            params![task_id],
+            |row| {
+                Ok(Progress {
+                    total: row.get(0)?,
+                    done: row.get(1)?,
+                })
+            },
+        )?;
+
+        Ok(progress)
+    }
+
+    /// Check where a task may go in the project tree and return the project
+    /// it ends up in. `project_id` is `None` when the caller didn't ask for
+    /// one; subtasks then inherit their parent's project.
+    pub(crate) fn resolve_placement(
+        &self,
+        task_id: Option<&str>,
+        project_id: Option<Option<&str>>,
+        parent_id: Option<&str>,
+    ) -> Result<Option<String>> {
+        let Some(parent_id) = parent_id else {
+            let Some(project_id) = project_id.flatten() else {
+                return Ok(None);
+            };
+            return Ok(Some(self.get_project(project_id)?.id));
+        };
+
+        let parent = self
+            .get_task(parent_id)
+            .map_err(|_| anyhow!("Parent task {} not found", parent_id))?;
+
+        if let Some(task_id) = task_id {
+            let is_cycle: bool = self.conn().query_row(
+                "WITH RECURSIVE ancestors(id) AS (
+                    SELECT ?1
+                    UNION
+                    SELECT tasks.parent_id FROM tasks JOIN ancestors ON tasks.id = ancestors.id
+                    WHERE tasks.parent_id IS NOT NULL
+                 )
+                 SELECT EXISTS(SELECT 1 FROM ancestors WHERE id = ?2)",
+                params![parent_id, task_id],
+                |row| row.get(0),
+            )?;
+            if is_cycle {
+                bail!("A task cannot be nested under itself or its own subtasks");
+            }
+        }
+
+        if project_id.is_some_and(|project_id| project_id != parent.project_id.as_deref()) {
+            bail!("A subtask belongs to its parent's project");
+        }
+
+        Ok(parent.project_id)
+    }
+
+    /// Move every subtask below a task into the given project
+    pub(crate) fn move_subtasks(&self, task_id: &str, project_id: Option<&str>) -> Result<()> {
+        self.conn().execute(
+            "WITH RECURSIVE subtree(id) AS (
+                SELECT id FROM tasks WHERE parent_id = ?1
+                UNION
+                SELECT tasks.id FROM tasks JOIN subtree ON tasks.parent_id = subtree.id
+             )
+             UPDATE tasks SET project_id = ?2, sync_version = sync_version + 1
+             WHERE id IN subtree AND project_id IS NOT ?2",
+            params![task_id, project_id],
+        )?;
+
+        Ok(())
+    }
+
+    fn summarize_project(&self, project: Project) -> Result<ProjectSummary> {
+        let progress = self.conn().query_row(
+            "SELECT COUNT(*), COUNT(CASE WHEN status = 'done' THEN 1 END)
+             FROM tasks WHERE project_id = ?1 AND deleted_at IS NULL",
+            params![project.id],
+            |row| {
+                Ok(Progress {
+                    total: row.get(0)?,
+                    done: row.get(1)?,
+                })
+            },
+        )?;
+
+        let next_action = self
+            .conn()
+            .query_row(
+                &format!(
+                    "SELECT {} FROM tasks
+                     WHERE project_id = ?1 AND status IN ('now', 'next') AND deleted_at IS NULL
+                     ORDER BY status = 'next', created_at ASC
+                     LIMIT 1",
+                    TASK_COLUMNS
+                ),
+                params![project.id],
+                |row| self.row_to_task(row),
+            )
+            .optional()?;
+
+        Ok(ProjectSummary {
+            needs_next_action: project.status == ProjectStatus::Active && next_action.is_none(),
+            project,
+            progress,
+            next_action,
+        })
+    }
+}
+
+/// Helper: Convert database row to Project
+fn row_to_project(row: &Row) -> rusqlite::Result<Project> {
+    Ok(Project {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        status: ProjectStatus::parse(&row.get::<_, String>(2)?).unwrap_or_default(),
+        notes: row.get(3)?,
+        created_at: row.get(4)?,
+        updated_at: row.get(5)?,
+        completed_at: row.get(6)?,
+        sync_version: row.get(7)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{NewTask, TaskPatch, TaskStatus};
+    use tempfile::{tempdir, TempDir};
+
+    fn setup_test_db() -> (TempDir, Database) {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let db = Database::new(db_path).unwrap();
+        (dir, db)
+    }
+
+    fn insert(
+        db: &Database,
+        title: &str,
+        project_id: Option<&str>,
+        parent_id: Option<&str>,
+    ) -> Task {
+        db.insert_task(&NewTask {
+            title: title.to_string(),
+            source: "test".to_string(),
+            project_id: project_id.map(str::to_string),
+            parent_id: parent_id.map(str::to_string),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_subtasks_and_progress() {
+        let (_dir, db) = setup_test_db();
+        let project = db.create_project(" Move house ", None).unwrap();
+        assert_eq!(project.title, "Move house");
+
+        let pack = insert(&db, "Pack", Some(&project.id), None);
+        let kitchen = insert(&db, "Kitchen", None, Some(&pack.id));
+        let plates = insert(&db, "Plates", None, Some(&kitchen.id));
+        insert(&db, "Books", None, Some(&pack.id));
+
+        // Subtasks inherit the project of their parent
+        assert_eq!(plates.project_id, Some(project.id.clone()));
+        assert_eq!(db.get_subtasks(&pack.id).unwrap().len(), 2);
+
+        db.update_task_status(&plates.id, TaskStatus::Done).unwrap();
+        assert_eq!(
+            db.get_task_progress(&pack.id).unwrap(),
+            Progress { total: 3, done: 1 }
+        );
+
+        let summary = db.get_project_summary(&project.id).unwrap();
+        assert_eq!(summary.progress, Progress { total: 4, done: 1 });
+        assert_eq!(summary.next_action.unwrap().id, pack.id);
+        assert!(!summary.needs_next_action);
+
+        // A subtask can't name a different project than its parent
+        let other = db.create_project("Other", None).unwrap();
+        assert!(db
+            .insert_task(&NewTask {
+                title: "Stray".to_string(),
+                source: "test".to_string(),
+                project_id: Some(other.id.clone()),
+                parent_id: Some(pack.id.clone()),
+                ..Default::default()
+            })
+            .is_err());
+    }
+
+    #[test]
+    fn test_moving_tasks() {
+        let (_dir, db) = setup_test_db();
+        let project = db.create_project("Launch", None).unwrap();
+        let parent = insert(&db, "Write docs", None, None);
+        let child = insert(&db, "API reference", None, Some(&parent.id));
+        let grandchild = insert(&db, "Examples", None, Some(&child.id));
+
+        // Moving a task carries its whole subtree along
+        db.update_task(
+            &parent.id,
+            TaskPatch {
+                project_id: Some(Some(project.id.clone())),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            db.get_task(&grandchild.id).unwrap().project_id,
+            Some(project.id.clone())
+        );
+
+        // Subtasks can't leave their parent's project on their own
+        let leave = TaskPatch {
+            project_id: Some(None),
+            ..Default::default()
+        };
+        assert!(db.update_task(&child.id, leave).is_err());
+
+        // Nesting a task under its own subtask would make a cycle
+        let cycle = TaskPatch {
+            parent_id: Some(Some(grandchild.id.clone())),
+            ..Default::default()
+        };
+        assert!(db.update_task(&parent.id, cycle).is_err());
+
+        // Detaching keeps the project; clearing both leaves it loose
+        let detach = TaskPatch {
+            parent_id: Some(None),
+            project_id: Some(None),
+            ..Default::default()
+        };
+        let child = db.update_task(&child.id, detach).unwrap();
+        assert_eq!(child.parent_id, None);
+        assert_eq!(child.project_id, None);
+        assert_eq!(db.get_task(&grandchild.id).unwrap().project_id, None);
+
+        let missing = TaskPatch {
+            project_id: Some(Some("nope".to_string())),
+            ..Default::default()
+        };
+        assert!(db.update_task(&child.id, missing).is_err());
+    }
+
+    #[test]
+    fn test_delete_cascades() {
+        let (_dir, db) = setup_test_db();
+        let project = db.create_project("Garden", None).unwrap();
+        let dig = insert(&db, "Dig beds", Some(&project.id), None);
+        let edge = insert(&db, "Edge lawn", None, Some(&dig.id));
+        let loose = insert(&db, "Buy seeds", None, None);
+        let seed_list = insert(&db, "Write list", None, Some(&loose.id));
+
+        db.delete_task(&loose.id).unwrap();
+        assert!(db.get_task(&seed_list.id).is_err());
+
+        db.delete_project(&project.id).unwrap();
+        assert!(db.get_project(&project.id).is_err());
+        assert!(db.get_task(&dig.id).is_err());
+        assert!(db.get_task(&edge.id).is_err());
+
+        // The project's tasks are in the trash, out of the deleted project
+        assert_eq!(db.get_trash().unwrap().len(), 4);
+        db.restore_task(&dig.id).unwrap();
+        assert_eq!(db.get_task(&dig.id).unwrap().project_id, None);
+        assert!(db.get_task(&edge.id).is_ok());
+    }
+
+    #[test]
+    fn test_removing_project_row_keeps_tasks() {
+        let (_dir, db) = setup_test_db();
+        let project = db.create_project("Garden", None).unwrap();
+        let dig = insert(&db, "Dig beds", Some(&project.id), None);
+        let edge = insert(&db, "Edge lawn", None, Some(&dig.id));
+
+        db.remove_project(&project.id).unwrap();
+        assert!(db.get_project(&project.id).is_err());
+
+        let dig_after = db.get_task(&dig.id).unwrap();
+        assert_eq!(dig_after.project_id, None);
+        assert_eq!(dig_after.deleted_at, None);
+        assert_eq!(dig_after.sync_version, dig.sync_version);
+        assert_eq!(db.get_task(&edge.id).unwrap().project_id, None);
+    }
+
+    #[test]
+    fn test_update_project_and_stalled() {
+        let (_dir, db) = setup_test_db();
+        let project = db.create_project("Taxes", Some("  2026 return ")).unwrap();
+        assert_eq!(project.notes.as_deref(), Some("2026 return"));
+
+        // An active project with no NEXT action is stalled
+        let task = insert(&db, "Gather receipts", Some(&project.id), None);
+        db.update_task_status(&task.id, TaskStatus::Waiting)
+            .unwrap();
+        let summaries = db.get_projects(Some(ProjectStatus::Active)).unwrap();
+        assert_eq!(summaries.len(), 1);
+        assert!(summaries[0].needs_next_action);
+
+        let done = db
+            .update_project(
+                &project.id,
+                ProjectPatch {
+                    status: Some(ProjectStatus::Done),
+                    notes: Some(None),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert!(done.completed_at.is_some());
+        assert_eq!(done.notes, None);
+        assert_eq!(done.sync_version, 2);
+        assert!(
+            !db.get_project_summary(&project.id)
+                .unwrap()
+                .needs_next_action
+        );
+        assert!(db
+            .get_projects(Some(ProjectStatus::Active))
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -1,10 +1,23 @@
-use anyhow::Result;
+use anyhow::{bail, Result};
 use chrono::Utc;
-use rusqlite::{params, Row};
+use chrono_tz::Tz;
+use rusqlite::types::Type;
+use rusqlite::{params, OptionalExtension, Row};
 use uuid::Uuid;
 
 use super::connection::Database;
-use super::models::{DashboardData, Task, TaskStatus};
+use super::contexts::normalize_context;
+use super::models::{DashboardData, Energy, NewTask, Priority, Task, TaskPatch, TaskStatus};
+use super::recurrence::Recurrence;
+use super::tags::normalize_tags;
+
+/// Column list matching the field order expected by `row_to_task`.
+/// Tags are folded into a comma-separated list from `task_tags`, so the
+/// `tasks` table must not be aliased in queries using it.
+pub(crate) const TASK_COLUMNS: &str = "id, title, status, context, scheduled_for, completed_at, created_at, updated_at, original_input, source,
+    (SELECT group_concat(tags.name, ',') FROM task_tags JOIN tags ON tags.id = task_tags.tag_id WHERE task_tags.task_id = tasks.id) AS tags,
+    sync_version, recurrence, waiting_on, waiting_since, follow_up_at, priority, effort_minutes, energy,
+    project_id, parent_id, deleted_at";
 
 impl Database {
     /// Create a new task
@@ -16,36 +29,82 @@ impl Database {
         scheduled_for: Option<i64>,
         original_input: Option<String>,
         source: String,
-        tags: Option<String>,
+        tags: Vec<String>,
     ) -> Result<Task> {
+        self.insert_task(&NewTask {
+            title,
+            status,
+            context,
+            scheduled_for,
+            original_input,
+            source,
+            tags,
+            ..Default::default()
+        })
+    }
+
+    /// Create a new task from a full set of fields
+    pub fn insert_task(&self, new_task: &NewTask) -> Result<Task> {
+        if let Some(recurrence) = &new_task.recurrence {
+            recurrence.validate()?;
+        }
+        if matches!(new_task.effort_minutes, Some(minutes) if minutes <= 0) {
+            bail!("Effort must be at least 1 minute");
+        }
+
         let id = Uuid::new_v4().to_string();
         let now = Utc::now().timestamp();
 
+        if new_task.status == TaskStatus::Now {
+            self.demote_now_task(None)?;
+        }
+
+        let project_id = self.resolve_placement(
+            None,
+            new_task.project_id.as_deref().map(Some),
+            new_task.parent_id.as_deref(),
+        )?;
+
+        let (waiting_since, follow_up_at) =
+            self.waiting_fields(&TaskStatus::Next, &new_task.status, None, None)?;
+
         self.conn().execute(
-            "INSERT INTO tasks (id, title, status, context, scheduled_for, created_at, updated_at, original_input, source, tags, sync_version)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, 1)",
+            "INSERT INTO tasks (id, title, status, context, scheduled_for, created_at, updated_at, original_input, source, sync_version, recurrence, waiting_on, waiting_since, follow_up_at, priority, effort_minutes, energy, project_id, parent_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, 1, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
             params![
                 id,
-                title,
-                status.as_str(),
-                context,
-                scheduled_for,
+                new_task.title,
+                new_task.status.as_str(),
+                new_task.context,
+                new_task.scheduled_for,
                 now,
                 now,
-                original_input,
-                source,
-                tags,
+                new_task.original_input,
+                new_task.source,
+                new_task.recurrence.as_ref().map(Recurrence::to_rrule),
+                new_task.waiting_on,
+                waiting_since,
+                follow_up_at,
+                new_task.priority.map(Priority::as_i32),
+                new_task.effort_minutes,
+                new_task.energy.as_ref().map(Energy::as_str),
+                project_id,
+                new_task.parent_id,
             ],
         )?;
 
+        self.set_task_tags(&id, &new_task.tags)?;
+
         self.get_task(&id)
     }
 
-    /// Get a task by ID
+    /// Get a task by ID. Tasks in the trash are not found.
     pub fn get_task(&self, id: &str) -> Result<Task> {
         let task = self.conn().query_row(
-            "SELECT id, title, status, context, scheduled_for, completed_at, created_at, updated_at, original_input, source, tags, sync_version
-             FROM tasks WHERE id = ?1",
+            &format!(
+                "SELECT {} FROM tasks WHERE id = ?1 AND deleted_at IS NULL",
+                TASK_COLUMNS
+            ),
             params![id],
             |row| self.row_to_task(row),
         )?;
@@ -56,50 +115,49 @@ impl Database {
     /// Get dashboard data (NOW task, NEXT tasks, WAITING tasks)
     pub fn get_dashboard_data(&self) -> Result<DashboardData> {
         // Get NOW task
-        let now_task = self.conn().query_row(
-            "SELECT id, title, status, context, scheduled_for, completed_at, created_at, updated_at, original_input, source, tags, sync_version
-             FROM tasks WHERE status = 'now' LIMIT 1",
-            [],
-            |row| self.row_to_task(row),
-        ).ok();
-
-        // Get NEXT tasks (limit 10)
-        let mut stmt = self.conn().prepare(
-            "SELECT id, title, status, context, scheduled_for, completed_at, created_at, updated_at, original_input, source, tags, sync_version
-             FROM tasks WHERE status = 'next' ORDER BY created_at DESC LIMIT 10"
-        )?;
+        let now_task = self
+            .conn()
+            .query_row(
+                &format!(
+                    "SELECT {} FROM tasks WHERE status = 'now' AND deleted_at IS NULL LIMIT 1",
+                    TASK_COLUMNS
+                ),
+                [],
+                |row| self.row_to_task(row),
+            )
+            .ok();
+
+        // In context mode, only tasks for that context or for anywhere
+        let current_context = self.current_context()?;
+
+        // Get NEXT tasks (limit 10): highest priority first, then quick wins,
+        // then newest. Tasks without a priority or estimate come after those with one.
+        let mut stmt = self.conn().prepare(&format!(
+            "SELECT {} FROM tasks
+             WHERE status = 'next' AND deleted_at IS NULL
+                AND (?1 IS NULL OR context = ?1 COLLATE NOCASE OR context IS NULL)
+             ORDER BY priority IS NULL, priority ASC, effort_minutes IS NULL, effort_minutes ASC,
+                created_at DESC
+             LIMIT 10",
+            TASK_COLUMNS
+        ))?;
 
         let next_tasks = stmt
-            .query_map([], |row| self.row_to_task(row))?
+            .query_map(params![current_context], |row| self.row_to_task(row))?
             .collect::<Result<Vec<_>, _>>()?;
 
-        // Get WAITING tasks (limit 10)
-        let mut stmt = self.conn().prepare(
-            "SELECT id, title, status, context, scheduled_for, completed_at, created_at, updated_at, original_input, source, tags, sync_version
-             FROM tasks WHERE status = 'waiting' ORDER BY created_at DESC LIMIT 10"
-        )?;
-
-        let waiting_tasks = stmt
-            .query_map([], |row| self.row_to_task(row))?
-            .collect::<Result<Vec<_>, _>>()?;
+        // Get WAITING tasks (limit 10), soonest follow-up first
+        let waiting_tasks = self.get_waiting_tasks(10, current_context.as_deref())?;
 
         // Calculate review due days
         let last_review: i64 = self
-            .conn()
-            .query_row(
-                "SELECT value FROM settings WHERE key = 'last_review_date'",
-                [],
-                |row| row.get(0),
-            )
+            .get_setting("last_review_date")?
+            .and_then(|s| s.parse().ok())
             .unwrap_or(0);
 
         let review_frequency: i64 = self
-            .conn()
-            .query_row(
-                "SELECT value FROM settings WHERE key = 'review_frequency_days'",
-                [],
-                |row| row.get::<_, String>(0).map(|s| s.parse().unwrap_or(7)),
-            )
+            .get_setting("review_frequency_days")?
+            .and_then(|s| s.parse().ok())
             .unwrap_or(7);
 
         let now = Utc::now().timestamp();
@@ -111,34 +169,289 @@ impl Database {
             next_tasks,
             waiting_tasks,
             review_due_in_days,
+            current_context,
         })
     }
 
-    /// Update task status
+    /// Update task status. Completing a recurring task schedules its next occurrence.
     pub fn update_task_status(&self, id: &str, status: TaskStatus) -> Result<()> {
+        let previous = self.get_task(id).ok();
+
         let completed_at = if status == TaskStatus::Done {
             Some(Utc::now().timestamp())
         } else {
             None
         };
 
+        let (waiting_since, follow_up_at) = match &previous {
+            Some(previous) => self.waiting_fields(
+                &previous.status,
+                &status,
+                previous.waiting_since,
+                previous.follow_up_at,
+            )?,
+            None => (None, None),
+        };
+
         self.conn().execute(
-            "UPDATE tasks SET status = ?1, completed_at = ?2, sync_version = sync_version + 1 WHERE id = ?3",
-            params![status.as_str(), completed_at, id],
+            "UPDATE tasks SET status = ?1, completed_at = ?2, waiting_since = ?3, follow_up_at = ?4,
+                sync_version = sync_version + 1
+             WHERE id = ?5 AND deleted_at IS NULL",
+            params![status.as_str(), completed_at, waiting_since, follow_up_at, id],
         )?;
 
+        if let Some(previous) = previous {
+            if status == TaskStatus::Done && previous.status != TaskStatus::Done {
+                self.spawn_next_occurrence(&previous)?;
+            }
+        }
+
         Ok(())
     }
 
-    /// Delete a task
+    /// Apply a partial edit to a task and return the updated task
+    pub fn update_task(&self, id: &str, patch: TaskPatch) -> Result<Task> {
+        let mut task = self.get_task(id)?;
+
+        if patch.is_empty() {
+            return Ok(task);
+        }
+
+        if let Some(title) = patch.title {
+            let title = title.trim();
+            if title.is_empty() {
+                bail!("Task title cannot be empty");
+            }
+            task.title = title.to_string();
+        }
+
+        if let Some(context) = patch.context {
+            task.context = match context {
+                Some(context) => Some(normalize_context(&context)?),
+                None => None,
+            };
+        }
+
+        if let Some(scheduled_for) = patch.scheduled_for {
+            if matches!(scheduled_for, Some(ts) if ts < 0) {
+                bail!("Invalid scheduled_for timestamp");
+            }
+            task.scheduled_for = scheduled_for;
+        }
+
+        let tags = match patch.tags {
+            Some(tags) => Some(normalize_tags(&tags)?),
+            None => None,
+        };
+
+        if let Some(recurrence) = patch.recurrence {
+            if let Some(recurrence) = &recurrence {
+                recurrence.validate()?;
+            }
+            task.recurrence = recurrence;
+        }
+
+        if let Some(waiting_on) = patch.waiting_on {
+            task.waiting_on = waiting_on
+                .map(|who| who.trim().to_string())
+                .filter(|who| !who.is_empty());
+        }
+
+        if let Some(priority) = patch.priority {
+            task.priority = priority;
+        }
+
+        if let Some(effort_minutes) = patch.effort_minutes {
+            if matches!(effort_minutes, Some(minutes) if minutes <= 0) {
+                bail!("Effort must be at least 1 minute");
+            }
+            task.effort_minutes = effort_minutes;
+        }
+
+        if let Some(energy) = patch.energy {
+            task.energy = energy;
+        }
+
+        let moved = patch.project_id.is_some() || patch.parent_id.is_some();
+        if moved {
+            if let Some(parent_id) = patch.parent_id {
+                task.parent_id = parent_id;
+            }
+            // Without an explicit project, subtasks follow their parent and
+            // everything else stays where it is
+            let project_id = match &patch.project_id {
+                Some(project_id) => Some(project_id.as_deref()),
+                None if task.parent_id.is_some() => None,
+                None => Some(task.project_id.as_deref()),
+            };
+            task.project_id =
+                self.resolve_placement(Some(id), project_id, task.parent_id.as_deref())?;
+        }
+
+        let previous_status = task.status.clone();
+
+        if let Some(status) = patch.status {
+            if status == TaskStatus::Done && task.status != TaskStatus::Done {
+                task.completed_at = Some(Utc::now().timestamp());
+            } else if status != TaskStatus::Done {
+                task.completed_at = None;
+            }
+            task.status = status;
+        }
+
+        if let Some(follow_up_at) = patch.follow_up_at {
+            if follow_up_at.is_some() && task.status != TaskStatus::Waiting {
+                bail!("Only WAITING tasks have a follow-up date");
+            }
+            if matches!(follow_up_at, Some(ts) if ts < 0) {
+                bail!("Invalid follow_up_at timestamp");
+            }
+            task.follow_up_at = follow_up_at;
+        }
+
+        (task.waiting_since, task.follow_up_at) = self.waiting_fields(
+            &previous_status,
+            &task.status,
+            task.waiting_since,
+            task.follow_up_at,
+        )?;
+
+        self.conn().execute(
+            "UPDATE tasks SET title = ?1, status = ?2, context = ?3, scheduled_for = ?4, completed_at = ?5,
+                recurrence = ?6, waiting_on = ?7, waiting_since = ?8, follow_up_at = ?9,
+                priority = ?10, effort_minutes = ?11, energy = ?12, project_id = ?13, parent_id = ?14,
+                sync_version = sync_version + 1
+             WHERE id = ?15",
+            params![
+                task.title,
+                task.status.as_str(),
+                task.context,
+                task.scheduled_for,
+                task.completed_at,
+                task.recurrence.as_ref().map(Recurrence::to_rrule),
+                task.waiting_on,
+                task.waiting_since,
+                task.follow_up_at,
+                task.priority.map(Priority::as_i32),
+                task.effort_minutes,
+                task.energy.as_ref().map(Energy::as_str),
+                task.project_id,
+                task.parent_id,
+                id,
+            ],
+        )?;
+
+        if moved {
+            self.move_subtasks(id, task.project_id.as_deref())?;
+        }
+
+        if let Some(tags) = tags {
+            self.set_task_tags(id, &tags)?;
+        }
+
+        if task.status == TaskStatus::Done && previous_status != TaskStatus::Done {
+            self.spawn_next_occurrence(&task)?;
+        }
+
+        self.get_task(id)
+    }
+
+    /// Move a task and its subtasks to the trash. They stay there, hidden
+    /// everywhere else, until restored or purged.
     pub fn delete_task(&self, id: &str) -> Result<()> {
-        self.conn()
-            .execute("DELETE FROM tasks WHERE id = ?1", params![id])?;
+        self.conn().execute(
+            "WITH RECURSIVE subtree(id) AS (
+                SELECT ?1
+                UNION
+                SELECT tasks.id FROM tasks JOIN subtree ON tasks.parent_id = subtree.id
+             )
+             UPDATE tasks SET deleted_at = ?2, sync_version = sync_version + 1
+             WHERE id IN subtree AND deleted_at IS NULL",
+            params![id, Utc::now().timestamp()],
+        )?;
+        Ok(())
+    }
+
+    /// Get a setting value by key
+    pub fn get_setting(&self, key: &str) -> Result<Option<String>> {
+        let value = self
+            .conn()
+            .query_row(
+                "SELECT value FROM settings WHERE key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        Ok(value)
+    }
+
+    /// Insert or update a setting value
+    pub fn set_setting(&self, key: &str, value: &str) -> Result<()> {
+        self.conn().execute(
+            "INSERT INTO settings (key, value, updated_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+            params![key, value, Utc::now().timestamp()],
+        )?;
+
+        Ok(())
+    }
+
+    /// The user's timezone from settings, falling back to the system
+    /// timezone and then UTC
+    pub fn timezone(&self) -> Result<Tz> {
+        let configured = self.get_setting("timezone")?.unwrap_or_default();
+
+        let tz = configured
+            .parse::<Tz>()
+            .ok()
+            .or_else(|| iana_time_zone::get_timezone().ok()?.parse().ok())
+            .unwrap_or(Tz::UTC);
+
+        Ok(tz)
+    }
+
+    /// Store the user's timezone (IANA name, e.g. "Europe/Berlin")
+    pub fn set_timezone(&self, name: &str) -> Result<Tz> {
+        let tz: Tz = name
+            .trim()
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Unknown timezone: {}", name))?;
+        self.set_setting("timezone", tz.name())?;
+        Ok(tz)
+    }
+
+    /// Quick-capture extractors to run, in order; `None` means the defaults
+    pub fn nlp_extractors(&self) -> Result<Option<Vec<String>>> {
+        match self.get_setting("nlp_extractors")? {
+            Some(value) if !value.trim().is_empty() => Ok(Some(serde_json::from_str(&value)?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Store the quick-capture extractors to run; `None` restores the defaults
+    pub fn set_nlp_extractors(&self, names: Option<&[String]>) -> Result<()> {
+        let value = match names {
+            Some(names) => serde_json::to_string(names)?,
+            None => String::new(),
+        };
+        self.set_setting("nlp_extractors", &value)
+    }
+
+    /// Helper: Move the NOW task, other than `keep_id`, back to NEXT. Inserts
+    /// bypass the enforce_one_now_task trigger, so they keep the rule with
+    /// this. The demotion is a change of its own and syncs like one.
+    pub(crate) fn demote_now_task(&self, keep_id: Option<&str>) -> Result<()> {
+        self.conn().execute(
+            "UPDATE tasks SET status = 'next', sync_version = sync_version + 1
+             WHERE status = 'now' AND deleted_at IS NULL AND id IS NOT ?1",
+            params![keep_id],
+        )?;
         Ok(())
     }
 
     /// Helper: Convert database row to Task
-    fn row_to_task(&self, row: &Row) -> rusqlite::Result<Task> {
+    pub(crate) fn row_to_task(&self, row: &Row) -> rusqlite::Result<Task> {
         Ok(Task {
             id: row.get(0)?,
             title: row.get(1)?,
@@ -150,8 +463,31 @@ impl Database {
             updated_at: row.get(7)?,
             original_input: row.get(8)?,
             source: row.get(9)?,
-            tags: row.get(10)?,
+            tags: row
+                .get::<_, Option<String>>(10)?
+                .map(|tags| {
+                    let mut tags: Vec<String> = tags.split(',').map(str::to_string).collect();
+                    tags.sort();
+                    tags
+                })
+                .unwrap_or_default(),
             sync_version: row.get(11)?,
+            recurrence: row
+                .get::<_, Option<String>>(12)?
+                .map(|rule| Recurrence::from_rrule(&rule))
+                .transpose()
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(12, Type::Text, e.into()))?,
+            waiting_on: row.get(13)?,
+            waiting_since: row.get(14)?,
+            follow_up_at: row.get(15)?,
+            priority: row.get::<_, Option<i32>>(16)?.and_then(Priority::from_i32),
+            effort_minutes: row.get(17)?,
+            energy: row
+                .get::<_, Option<String>>(18)?
+                .and_then(|energy| Energy::parse(&energy)),
+            project_id: row.get(19)?,
+            parent_id: row.get(20)?,
+            deleted_at: row.get(21)?,
         })
     }
 }
@@ -160,17 +496,20 @@ impl Database {
 mod tests {
     use super::*;
 
-    use tempfile::tempdir;
+    use tempfile::{tempdir, TempDir};
 
-    fn setup_test_db() -> Database {
+    /// Keep the TempDir alive alongside the database so the file isn't
+    /// removed out from under the open connection.
+    fn setup_test_db() -> (TempDir, Database) {
         let dir = tempdir().unwrap();
         let db_path = dir.path().join("test.db");
-        Database::new(db_path).unwrap()
+        let db = Database::new(db_path).unwrap();
+        (dir, db)
     }
 
     #[test]
     fn test_create_and_get_task() {
-        let db = setup_test_db();
+        let (_dir, db) = setup_test_db();
 
         let task = db
             .create_task(
@@ -180,7 +519,7 @@ mod tests {
                 None,
                 None,
                 "test".to_string(),
-                None,
+                Vec::new(),
             )
             .unwrap();
 
@@ -193,7 +532,7 @@ mod tests {
 
     #[test]
     fn test_dashboard_data() {
-        let db = setup_test_db();
+        let (_dir, db) = setup_test_db();
 
         // Create NOW task
         db.create_task(
@@ -203,7 +542,7 @@ mod tests {
             None,
             None,
             "test".to_string(),
-            None,
+            Vec::new(),
         )
         .unwrap();
 
@@ -215,7 +554,7 @@ mod tests {
             None,
             None,
             "test".to_string(),
-            None,
+            Vec::new(),
         )
         .unwrap();
 
@@ -226,9 +565,56 @@ mod tests {
         assert_eq!(dashboard.next_tasks.len(), 1);
     }
 
+    #[test]
+    fn test_dashboard_next_task_order() {
+        let (_dir, db) = setup_test_db();
+
+        let insert = |title: &str, priority, effort_minutes| {
+            db.insert_task(&NewTask {
+                title: title.to_string(),
+                source: "test".to_string(),
+                priority,
+                effort_minutes,
+                ..Default::default()
+            })
+            .unwrap()
+        };
+        insert("Unprioritized", None, None);
+        insert("Low", Some(Priority::Low), None);
+        insert("High, long", Some(Priority::High), Some(120));
+        insert("High, unestimated", Some(Priority::High), None);
+        insert("High, quick", Some(Priority::High), Some(10));
+
+        let titles: Vec<_> = db
+            .get_dashboard_data()
+            .unwrap()
+            .next_tasks
+            .into_iter()
+            .map(|t| t.title)
+            .collect();
+        assert_eq!(
+            titles,
+            vec![
+                "High, quick",
+                "High, long",
+                "High, unestimated",
+                "Low",
+                "Unprioritized"
+            ]
+        );
+
+        assert!(db
+            .insert_task(&NewTask {
+                title: "Bad".to_string(),
+                effort_minutes: Some(0),
+                ..Default::default()
+            })
+            .is_err());
+    }
+
     #[test]
     fn test_one_now_task_enforcement() {
-        let db = setup_test_db();
+        let (_dir, db) = setup_test_db();
 
         // Create first NOW task
         let task1 = db
@@ -239,7 +625,7 @@ mod tests {
                 None,
                 None,
                 "test".to_string(),
-                None,
+                Vec::new(),
             )
             .unwrap();
 
@@ -252,7 +638,7 @@ mod tests {
                 None,
                 None,
                 "test".to_string(),
-                None,
+                Vec::new(),
             )
             .unwrap();
 
@@ -267,4 +653,154 @@ mod tests {
         let task2_updated = db.get_task(&task2.id).unwrap();
         assert_eq!(task2_updated.status, TaskStatus::Now);
     }
+
+    #[test]
+    fn test_insert_now_task_demotes_current() {
+        let (_dir, db) = setup_test_db();
+
+        let first = db
+            .insert_task(&NewTask {
+                title: "First".to_string(),
+                status: TaskStatus::Now,
+                source: "test".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        let second = db
+            .insert_task(&NewTask {
+                title: "Second".to_string(),
+                status: TaskStatus::Now,
+                source: "test".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+
+        // The demoted task syncs as changed
+        let demoted = db.get_task(&first.id).unwrap();
+        assert_eq!(demoted.status, TaskStatus::Next);
+        assert_eq!(demoted.sync_version, first.sync_version + 1);
+        assert_eq!(db.get_task(&second.id).unwrap().status, TaskStatus::Now);
+
+        // A NOW task in the trash is left as it is
+        db.delete_task(&second.id).unwrap();
+        db.insert_task(&NewTask {
+            title: "Third".to_string(),
+            status: TaskStatus::Now,
+            source: "test".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        let trashed = &db.get_trash().unwrap()[0];
+        assert_eq!(trashed.status, TaskStatus::Now);
+        assert_eq!(trashed.sync_version, 2);
+
+        let waiting = db
+            .insert_task(&NewTask {
+                title: "Contract".to_string(),
+                status: TaskStatus::Waiting,
+                source: "test".to_string(),
+                waiting_on: Some("Alice".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(waiting.waiting_on, Some("Alice".to_string()));
+    }
+
+    #[test]
+    fn test_update_task_patch() {
+        let (_dir, db) = setup_test_db();
+
+        let task = db
+            .create_task(
+                "Draft report".to_string(),
+                TaskStatus::Next,
+                Some("office".to_string()),
+                Some(1_000),
+                None,
+                "test".to_string(),
+                Vec::new(),
+            )
+            .unwrap();
+
+        let patch: TaskPatch = serde_json::from_str(
+            r#"{"title": "  Final report ", "context": null, "status": "done"}"#,
+        )
+        .unwrap();
+        let updated = db.update_task(&task.id, patch).unwrap();
+
+        assert_eq!(updated.title, "Final report");
+        assert_eq!(updated.context, None);
+        assert_eq!(updated.scheduled_for, Some(1_000));
+        assert_eq!(updated.status, TaskStatus::Done);
+        assert!(updated.completed_at.is_some());
+        assert_eq!(updated.sync_version, task.sync_version + 1);
+
+        // Reopening clears completed_at
+        let reopened = db
+            .update_task(
+                &task.id,
+                TaskPatch {
+                    status: Some(TaskStatus::Next),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert!(reopened.completed_at.is_none());
+    }
+
+    #[test]
+    fn test_update_task_validation() {
+        let (_dir, db) = setup_test_db();
+
+        let task = db
+            .create_task(
+                "Task".to_string(),
+                TaskStatus::Next,
+                None,
+                None,
+                None,
+                "test".to_string(),
+                Vec::new(),
+            )
+            .unwrap();
+
+        let empty_title = TaskPatch {
+            title: Some("   ".to_string()),
+            ..Default::default()
+        };
+        assert!(db.update_task(&task.id, empty_title).is_err());
+
+        let bad_context = TaskPatch {
+            context: Some(Some("two words".to_string())),
+            ..Default::default()
+        };
+        assert!(db.update_task(&task.id, bad_context).is_err());
+
+        // Failed edits leave the task untouched
+        let unchanged = db.get_task(&task.id).unwrap();
+        assert_eq!(unchanged.title, "Task");
+        assert_eq!(unchanged.sync_version, task.sync_version);
+
+        assert!(db.update_task("missing", TaskPatch::default()).is_err());
+    }
+
+    #[test]
+    fn test_settings_timezone_and_extractors() {
+        let (_dir, db) = setup_test_db();
+
+        assert!(db.set_timezone("Mars/Olympus_Mons").is_err());
+        db.set_timezone("Europe/Berlin").unwrap();
+        assert_eq!(db.timezone().unwrap(), chrono_tz::Europe::Berlin);
+
+        assert_eq!(db.nlp_extractors().unwrap(), None);
+        let order = vec!["dates".to_string(), "context".to_string()];
+        db.set_nlp_extractors(Some(&order)).unwrap();
+        assert_eq!(db.nlp_extractors().unwrap(), Some(order));
+
+        // An empty list disables every extractor rather than restoring the defaults
+        db.set_nlp_extractors(Some(&[])).unwrap();
+        assert_eq!(db.nlp_extractors().unwrap(), Some(vec![]));
+        db.set_nlp_extractors(None).unwrap();
+        assert_eq!(db.nlp_extractors().unwrap(), None);
+    }
 }
@@ -0,0 +1,452 @@
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use super::connection::Database;
+use super::models::{NewTask, Task, TaskStatus};
+
+/// How a task repeats once it is completed.
+///
+/// Stored in `tasks.recurrence` as an RRULE-style string, e.g.
+/// `FREQ=WEEKLY;INTERVAL=1;BYDAY=MO,TH`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Recurrence {
+    /// Every `interval` days on the schedule
+    Daily { interval: u32 },
+    /// Every `interval` weeks on the given weekdays (empty = the scheduled weekday)
+    Weekly {
+        interval: u32,
+        weekdays: Vec<Weekday>,
+    },
+    /// Every `interval` months on day `day` (clamped to the month's length)
+    Monthly { interval: u32, day: u32 },
+    /// `days` days after the task was actually completed
+    AfterCompletion { days: u32 },
+}
+
+const RRULE_DAYS: [(&str, Weekday); 7] = [
+    ("MO", Weekday::Mon),
+    ("TU", Weekday::Tue),
+    ("WE", Weekday::Wed),
+    ("TH", Weekday::Thu),
+    ("FR", Weekday::Fri),
+    ("SA", Weekday::Sat),
+    ("SU", Weekday::Sun),
+];
+
+impl Recurrence {
+    /// Format as an RRULE-style string
+    pub fn to_rrule(&self) -> String {
+        match self {
+            Recurrence::Daily { interval } => format!("FREQ=DAILY;INTERVAL={}", interval),
+            Recurrence::Weekly { interval, weekdays } => {
+                let mut rule = format!("FREQ=WEEKLY;INTERVAL={}", interval);
+                if !weekdays.is_empty() {
+                    let days: Vec<&str> = RRULE_DAYS
+                        .iter()
+                        .filter(|(_, day)| weekdays.contains(day))
+                        .map(|(code, _)| *code)
+                        .collect();
+                    rule += &format!(";BYDAY={}", days.join(","));
+                }
+                rule
+            }
+            Recurrence::Monthly { interval, day } => {
+                format!("FREQ=MONTHLY;INTERVAL={};BYMONTHDAY={}", interval, day)
+            }
+            Recurrence::AfterCompletion { days } => {
+                format!("FREQ=DAILY;INTERVAL={};X-ANCHOR=COMPLETION", days)
+            }
+        }
+    }
+
+    /// Parse an RRULE-style string produced by `to_rrule`
+    pub fn from_rrule(rule: &str) -> Result<Self> {
+        let mut freq = None;
+        let mut interval = 1;
+        let mut weekdays = Vec::new();
+        let mut month_day = None;
+        let mut after_completion = false;
+
+        for part in rule.split(';').filter(|p| !p.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| anyhow!("Invalid recurrence rule: {}", rule))?;
+
+            match key {
+                "FREQ" => freq = Some(value.to_string()),
+                "INTERVAL" => interval = value.parse()?,
+                "BYDAY" => {
+                    for code in value.split(',') {
+                        let day = RRULE_DAYS
+                            .iter()
+                            .find(|(c, _)| *c == code)
+                            .map(|(_, day)| *day)
+                            .ok_or_else(|| anyhow!("Invalid weekday in recurrence: {}", code))?;
+                        weekdays.push(day);
+                    }
+                }
+                "BYMONTHDAY" => month_day = Some(value.parse()?),
+                "X-ANCHOR" => after_completion = value == "COMPLETION",
+                _ => bail!("Unsupported recurrence rule part: {}", part),
+            }
+        }
+
+        let recurrence = match freq.as_deref() {
+            Some("DAILY") if after_completion => Recurrence::AfterCompletion { days: interval },
+            Some("DAILY") => Recurrence::Daily { interval },
+            Some("WEEKLY") => Recurrence::Weekly { interval, weekdays },
+            Some("MONTHLY") => Recurrence::Monthly {
+                interval,
+                day: month_day.ok_or_else(|| anyhow!("Monthly recurrence needs BYMONTHDAY"))?,
+            },
+            _ => bail!("Invalid recurrence rule: {}", rule),
+        };
+
+        recurrence.validate()?;
+        Ok(recurrence)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            Recurrence::Daily { interval }
+            | Recurrence::Weekly { interval, .. }
+            | Recurrence::Monthly { interval, .. }
+            | Recurrence::AfterCompletion { days: interval } => {
+                if *interval == 0 {
+                    bail!("Recurrence interval must be at least 1");
+                }
+            }
+        }
+
+        if let Recurrence::Monthly { day, .. } = self {
+            if !(1..=31).contains(day) {
+                bail!("Monthly recurrence day must be between 1 and 31");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// When the next occurrence is due, given the current occurrence's
+    /// schedule and when it was completed.
+    ///
+    /// Schedule-based rules step forward from `scheduled_for` (or the
+    /// completion time if the task had no schedule), skipping occurrences
+    /// that are already in the past. Dates are stepped in the timezone of the
+    /// inputs so the local time of day is kept across DST changes.
+    pub fn next_occurrence<Tz: TimeZone>(
+        &self,
+        scheduled_for: Option<DateTime<Tz>>,
+        completed_at: DateTime<Tz>,
+    ) -> DateTime<Tz> {
+        let base = scheduled_for.unwrap_or_else(|| completed_at.clone());
+        let base_date = base.date_naive();
+
+        match self {
+            Recurrence::AfterCompletion { days } => {
+                let date = completed_at.date_naive() + Duration::days(*days as i64);
+                at_time_of(date, &base)
+            }
+            Recurrence::Daily { interval } => {
+                let mut date = base_date;
+                loop {
+                    date += Duration::days(*interval as i64);
+                    let candidate = at_time_of(date, &base);
+                    if candidate > completed_at {
+                        return candidate;
+                    }
+                }
+            }
+            Recurrence::Weekly { interval, weekdays } => {
+                let weekdays = if weekdays.is_empty() {
+                    vec![base_date.weekday()]
+                } else {
+                    weekdays.clone()
+                };
+                // Weeks are counted from the Monday of the base week
+                let week_start =
+                    base_date - Duration::days(base_date.weekday().num_days_from_monday() as i64);
+
+                let mut date = base_date;
+                loop {
+                    date += Duration::days(1);
+                    let week = (date - week_start).num_days() / 7;
+                    if week % *interval as i64 != 0 || !weekdays.contains(&date.weekday()) {
+                        continue;
+                    }
+                    let candidate = at_time_of(date, &base);
+                    if candidate > completed_at {
+                        return candidate;
+                    }
+                }
+            }
+            Recurrence::Monthly { interval, day } => {
+                let mut months = 0;
+                loop {
+                    months += *interval as i32;
+                    let candidate = at_time_of(add_months(base_date, months, *day), &base);
+                    if candidate > completed_at {
+                        return candidate;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Database {
+    /// Create the next occurrence of a recurring task that was just completed.
+    /// The rule moves to the new task so reopening and re-completing the old
+    /// one doesn't spawn a duplicate.
+    pub(crate) fn spawn_next_occurrence(&self, completed: &Task) -> Result<Option<Task>> {
+        let Some(recurrence) = &completed.recurrence else {
+            return Ok(None);
+        };
+
+        let tz = self.timezone()?;
+        let scheduled_for = completed
+            .scheduled_for
+            .and_then(|ts| DateTime::from_timestamp(ts, 0))
+            .map(|dt| dt.with_timezone(&tz));
+        let next = recurrence.next_occurrence(scheduled_for, Utc::now().with_timezone(&tz));
+
+        let task = self.with_event_source("recurrence", |db| {
+            let task = db.insert_task(&NewTask {
+                title: completed.title.clone(),
+                status: TaskStatus::Next,
+                context: completed.context.clone(),
+                scheduled_for: Some(next.timestamp()),
+                original_input: completed.original_input.clone(),
+                source: "recurrence".to_string(),
+                tags: completed.tags.clone(),
+                recurrence: Some(recurrence.clone()),
+                waiting_on: None,
+                priority: completed.priority,
+                effort_minutes: completed.effort_minutes,
+                energy: completed.energy,
+                project_id: completed.project_id.clone(),
+                parent_id: completed.parent_id.clone(),
+            })?;
+
+            db.conn().execute(
+                "UPDATE tasks SET recurrence = NULL WHERE id = ?1",
+                params![completed.id],
+            )?;
+
+            Ok(task)
+        })?;
+
+        Ok(Some(task))
+    }
+}
+
+/// Combine a date with the local time of day of another timestamp
+fn at_time_of<Tz: TimeZone>(date: NaiveDate, time: &DateTime<Tz>) -> DateTime<Tz> {
+    local_datetime(&time.timezone(), date.and_time(time.time()))
+}
+
+/// Resolve a wall-clock time in a timezone. Ambiguous times (DST fall-back)
+/// take the earlier instant; times skipped by a DST jump move forward an hour.
+pub fn local_datetime<Tz: TimeZone>(tz: &Tz, local: NaiveDateTime) -> DateTime<Tz> {
+    tz.from_local_datetime(&local)
+        .earliest()
+        .or_else(|| {
+            tz.from_local_datetime(&(local + Duration::hours(1)))
+                .earliest()
+        })
+        .unwrap_or_else(|| tz.from_utc_datetime(&local))
+}
+
+/// Day `day` of the month `months` after `date`'s month, clamped to the month length
+fn add_months(date: NaiveDate, months: i32, day: u32) -> NaiveDate {
+    let total = date.year() * 12 + date.month0() as i32 + months;
+    let (year, month) = (total.div_euclid(12), total.rem_euclid(12) as u32 + 1);
+
+    (1..=day)
+        .rev()
+        .find_map(|d| NaiveDate::from_ymd_opt(year, month, d))
+        .expect("every month has a first day")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::TaskPatch;
+    use tempfile::{tempdir, TempDir};
+
+    fn setup_test_db() -> (TempDir, Database) {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let db = Database::new(db_path).unwrap();
+        (dir, db)
+    }
+
+    fn at(y: i32, m: u32, d: u32, h: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_rrule_round_trip() {
+        let rules = [
+            Recurrence::Daily { interval: 3 },
+            Recurrence::Weekly {
+                interval: 2,
+                weekdays: vec![Weekday::Mon, Weekday::Thu],
+            },
+            Recurrence::Weekly {
+                interval: 1,
+                weekdays: vec![],
+            },
+            Recurrence::Monthly {
+                interval: 1,
+                day: 31,
+            },
+            Recurrence::AfterCompletion { days: 10 },
+        ];
+
+        for rule in rules {
+            assert_eq!(Recurrence::from_rrule(&rule.to_rrule()).unwrap(), rule);
+        }
+
+        assert_eq!(
+            Recurrence::Weekly {
+                interval: 1,
+                weekdays: vec![Weekday::Thu, Weekday::Mon],
+            }
+            .to_rrule(),
+            "FREQ=WEEKLY;INTERVAL=1;BYDAY=MO,TH"
+        );
+        assert!(Recurrence::from_rrule("FREQ=YEARLY").is_err());
+        assert!(Recurrence::from_rrule("FREQ=DAILY;INTERVAL=0").is_err());
+    }
+
+    #[test]
+    fn test_daily_skips_missed_occurrences() {
+        let rule = Recurrence::Daily { interval: 2 };
+        // Scheduled Mon 9:00, completed late on Thursday
+        let next = rule.next_occurrence(Some(at(2026, 3, 2, 9)), at(2026, 3, 5, 20));
+        assert_eq!(next, at(2026, 3, 6, 9));
+    }
+
+    #[test]
+    fn test_weekly_on_weekdays() {
+        let rule = Recurrence::Weekly {
+            interval: 1,
+            weekdays: vec![Weekday::Mon, Weekday::Thu],
+        };
+        // Monday 2026-03-02 -> Thursday 2026-03-05
+        let next = rule.next_occurrence(Some(at(2026, 3, 2, 9)), at(2026, 3, 2, 10));
+        assert_eq!(next, at(2026, 3, 5, 9));
+
+        let biweekly = Recurrence::Weekly {
+            interval: 2,
+            weekdays: vec![],
+        };
+        let next = biweekly.next_occurrence(Some(at(2026, 3, 2, 9)), at(2026, 3, 2, 10));
+        assert_eq!(next, at(2026, 3, 16, 9));
+    }
+
+    #[test]
+    fn test_monthly_clamps_day() {
+        let rule = Recurrence::Monthly {
+            interval: 1,
+            day: 31,
+        };
+        let next = rule.next_occurrence(Some(at(2026, 1, 31, 9)), at(2026, 1, 31, 12));
+        assert_eq!(next, at(2026, 2, 28, 9));
+    }
+
+    #[test]
+    fn test_keeps_local_time_across_dst() {
+        let tz = chrono_tz::Europe::Berlin;
+        let rule = Recurrence::Daily { interval: 1 };
+
+        // Clocks go forward overnight on 2026-03-29
+        let scheduled = tz.with_ymd_and_hms(2026, 3, 28, 9, 0, 0).unwrap();
+        let next = rule.next_occurrence(Some(scheduled), scheduled);
+
+        assert_eq!(next, tz.with_ymd_and_hms(2026, 3, 29, 9, 0, 0).unwrap());
+        assert_eq!((next - scheduled).num_hours(), 23);
+    }
+
+    #[test]
+    fn test_after_completion() {
+        let rule = Recurrence::AfterCompletion { days: 3 };
+        let next = rule.next_occurrence(Some(at(2026, 3, 1, 9)), at(2026, 3, 10, 18));
+        assert_eq!(next, at(2026, 3, 13, 9));
+    }
+
+    #[test]
+    fn test_completing_recurring_task_spawns_next() {
+        let (_dir, db) = setup_test_db();
+
+        let scheduled = Utc::now() - Duration::hours(1);
+        let task = db
+            .insert_task(&NewTask {
+                title: "Water plants".to_string(),
+                scheduled_for: Some(scheduled.timestamp()),
+                source: "test".to_string(),
+                tags: vec!["home".to_string()],
+                recurrence: Some(Recurrence::Daily { interval: 1 }),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(task.recurrence, Some(Recurrence::Daily { interval: 1 }));
+
+        db.update_task_status(&task.id, TaskStatus::Done).unwrap();
+
+        let completed = db.get_task(&task.id).unwrap();
+        assert!(completed.recurrence.is_none());
+
+        let next = db.get_all_tasks().unwrap();
+        let next = next.iter().find(|t| t.id != task.id).unwrap();
+        assert_eq!(next.title, "Water plants");
+        assert_eq!(next.status, TaskStatus::Next);
+        assert_eq!(next.tags, vec!["home"]);
+        assert_eq!(next.recurrence, Some(Recurrence::Daily { interval: 1 }));
+        assert_eq!(
+            next.scheduled_for,
+            Some((scheduled + Duration::days(1)).timestamp())
+        );
+
+        // Completing again doesn't spawn a second copy
+        db.update_task_status(&task.id, TaskStatus::Done).unwrap();
+        assert_eq!(db.get_all_tasks().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_recurrence_via_patch() {
+        let (_dir, db) = setup_test_db();
+
+        let task = db
+            .insert_task(&NewTask {
+                title: "Pay rent".to_string(),
+                source: "test".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let patch: TaskPatch = serde_json::from_str(
+            r#"{"recurrence": {"kind": "monthly", "interval": 1, "day": 1}, "status": "done"}"#,
+        )
+        .unwrap();
+        db.update_task(&task.id, patch).unwrap();
+
+        let tasks = db.get_all_tasks().unwrap();
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(
+            tasks[1].recurrence,
+            Some(Recurrence::Monthly {
+                interval: 1,
+                day: 1
+            })
+        );
+
+        let invalid: TaskPatch =
+            serde_json::from_str(r#"{"recurrence": {"kind": "daily", "interval": 0}}"#).unwrap();
+        assert!(db.update_task(&tasks[1].id, invalid).is_err());
+    }
+}
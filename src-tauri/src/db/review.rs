@@ -0,0 +1,331 @@
+use anyhow::{bail, Result};
+use chrono::Utc;
+use rusqlite::{params, OptionalExtension, Row};
+use uuid::Uuid;
+
+use super::connection::Database;
+use super::models::{ProjectStatus, ProjectSummary, ReviewDecision, ReviewLog, Task, TaskStatus};
+use super::queries::TASK_COLUMNS;
+
+pub(crate) const REVIEW_COLUMNS: &str =
+    "id, started_at, completed_at, tasks_triaged, tasks_completed, tasks_deferred, tasks_deleted";
+
+impl Database {
+    /// Start a weekly review, or resume the one that is still open
+    pub fn start_review(&self) -> Result<ReviewLog> {
+        if let Some(review) = self.get_active_review()? {
+            return Ok(review);
+        }
+
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now().timestamp();
+
+        self.conn().execute(
+            "INSERT INTO review_log (id, started_at, tasks_triaged, tasks_completed, tasks_deferred, tasks_deleted)
+             VALUES (?1, ?2, 0, 0, 0, 0)",
+            params![id, now],
+        )?;
+
+        self.get_review(&id)
+    }
+
+    /// Get a review session by ID
+    pub fn get_review(&self, id: &str) -> Result<ReviewLog> {
+        let review = self.conn().query_row(
+            &format!("SELECT {} FROM review_log WHERE id = ?1", REVIEW_COLUMNS),
+            params![id],
+            row_to_review_log,
+        )?;
+
+        Ok(review)
+    }
+
+    /// Get the review session that has been started but not finished, if any
+    pub fn get_active_review(&self) -> Result<Option<ReviewLog>> {
+        let review = self
+            .conn()
+            .query_row(
+                &format!(
+                    "SELECT {} FROM review_log WHERE completed_at IS NULL ORDER BY started_at DESC LIMIT 1",
+                    REVIEW_COLUMNS
+                ),
+                [],
+                row_to_review_log,
+            )
+            .optional()?;
+
+        Ok(review)
+    }
+
+    /// Get every task that still needs triage (everything not DONE),
+    /// grouped by status in NOW, NEXT, WAITING, SOMEDAY order
+    pub fn get_review_tasks(&self) -> Result<Vec<Task>> {
+        let mut stmt = self.conn().prepare(&format!(
+            "SELECT {} FROM tasks WHERE status != 'done' AND deleted_at IS NULL
+             ORDER BY CASE status
+                 WHEN 'now' THEN 0
+                 WHEN 'next' THEN 1
+                 WHEN 'waiting' THEN 2
+                 ELSE 3
+             END, created_at ASC",
+            TASK_COLUMNS
+        ))?;
+
+        let tasks = stmt
+            .query_map([], |row| self.row_to_task(row))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(tasks)
+    }
+
+    /// Active projects to check during the review, stalled ones (with no
+    /// NOW or NEXT action) first
+    pub fn get_review_projects(&self) -> Result<Vec<ProjectSummary>> {
+        let mut projects = self.get_projects(Some(ProjectStatus::Active))?;
+        projects.sort_by_key(|summary| !summary.needs_next_action);
+        Ok(projects)
+    }
+
+    /// Apply a triage decision to a task and count it against the review.
+    /// Each task is triaged at most once per review.
+    pub fn record_review_decision(
+        &self,
+        review_id: &str,
+        task_id: &str,
+        decision: ReviewDecision,
+    ) -> Result<ReviewLog> {
+        let review = self.get_review(review_id)?;
+        if review.completed_at.is_some() {
+            bail!("Review {} is already finished", review_id);
+        }
+
+        // Make sure the task exists before touching any counters
+        self.get_task(task_id)?;
+
+        let tx = self.conn().unchecked_transaction()?;
+        let first = self.conn().execute(
+            "INSERT OR IGNORE INTO review_triage (review_id, task_id, triaged_at)
+             VALUES (?1, ?2, ?3)",
+            params![review_id, task_id, Utc::now().timestamp()],
+        )?;
+        if first == 0 {
+            bail!("Task {} was already triaged in this review", task_id);
+        }
+
+        let counter = self.with_event_source("review", |db| {
+            Ok(match decision {
+                ReviewDecision::Keep => None,
+                ReviewDecision::Complete => {
+                    db.update_task_status(task_id, TaskStatus::Done)?;
+                    Some("tasks_completed")
+                }
+                ReviewDecision::Defer => {
+                    db.update_task_status(task_id, TaskStatus::Someday)?;
+                    Some("tasks_deferred")
+                }
+                ReviewDecision::Delete => {
+                    db.delete_task(task_id)?;
+                    Some("tasks_deleted")
+                }
+            })
+        })?;
+
+        match counter {
+            Some(column) => self.conn().execute(
+                &format!(
+                    "UPDATE review_log SET tasks_triaged = tasks_triaged + 1, {0} = {0} + 1 WHERE id = ?1",
+                    column
+                ),
+                params![review_id],
+            )?,
+            None => self.conn().execute(
+                "UPDATE review_log SET tasks_triaged = tasks_triaged + 1 WHERE id = ?1",
+                params![review_id],
+            )?,
+        };
+
+        tx.commit()?;
+        self.get_review(review_id)
+    }
+
+    /// Finish a review and record it as the last review date
+    pub fn finish_review(&self, review_id: &str) -> Result<ReviewLog> {
+        let review = self.get_review(review_id)?;
+        if review.completed_at.is_some() {
+            return Ok(review);
+        }
+
+        let now = Utc::now().timestamp();
+
+        self.conn().execute(
+            "UPDATE review_log SET completed_at = ?1 WHERE id = ?2",
+            params![now, review_id],
+        )?;
+        self.set_setting("last_review_date", &now.to_string())?;
+
+        self.get_review(review_id)
+    }
+}
+
+/// Helper: Convert database row to ReviewLog
+pub(crate) fn row_to_review_log(row: &Row) -> rusqlite::Result<ReviewLog> {
+    Ok(ReviewLog {
+        id: row.get(0)?,
+        started_at: row.get(1)?,
+        completed_at: row.get(2)?,
+        tasks_triaged: row.get(3)?,
+        tasks_completed: row.get(4)?,
+        tasks_deferred: row.get(5)?,
+        tasks_deleted: row.get(6)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{ProjectPatch, TaskPatch};
+
+    use tempfile::{tempdir, TempDir};
+
+    fn setup_test_db() -> (TempDir, Database) {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let db = Database::new(db_path).unwrap();
+        (dir, db)
+    }
+
+    fn create(db: &Database, title: &str, status: TaskStatus) -> Task {
+        db.create_task(
+            title.to_string(),
+            status,
+            None,
+            None,
+            None,
+            "test".to_string(),
+            Vec::new(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_start_review_resumes_open_session() {
+        let (_dir, db) = setup_test_db();
+
+        let first = db.start_review().unwrap();
+        let second = db.start_review().unwrap();
+
+        assert_eq!(first.id, second.id);
+        assert!(second.completed_at.is_none());
+    }
+
+    #[test]
+    fn test_review_tasks_exclude_done() {
+        let (_dir, db) = setup_test_db();
+
+        create(&db, "Someday task", TaskStatus::Someday);
+        create(&db, "Next task", TaskStatus::Next);
+        create(&db, "Done task", TaskStatus::Done);
+
+        let tasks = db.get_review_tasks().unwrap();
+        let titles: Vec<_> = tasks.iter().map(|t| t.title.as_str()).collect();
+
+        assert_eq!(titles, vec!["Next task", "Someday task"]);
+    }
+
+    #[test]
+    fn test_review_projects_stalled_first() {
+        let (_dir, db) = setup_test_db();
+
+        let moving = db.create_project("Moving", None).unwrap();
+        let garden = db.create_project("Garden", None).unwrap();
+        let shelved = db.create_project("Novel", None).unwrap();
+        db.update_project(
+            &shelved.id,
+            ProjectPatch {
+                status: Some(ProjectStatus::Someday),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let task = create(&db, "Book van", TaskStatus::Next);
+        db.update_task(
+            &task.id,
+            TaskPatch {
+                project_id: Some(Some(moving.id.clone())),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let projects = db.get_review_projects().unwrap();
+        let ids: Vec<_> = projects.iter().map(|p| p.project.id.as_str()).collect();
+        assert_eq!(ids, vec![garden.id.as_str(), moving.id.as_str()]);
+        assert!(projects[0].needs_next_action);
+        assert!(!projects[1].needs_next_action);
+    }
+
+    #[test]
+    fn test_review_decisions_update_counters() {
+        let (_dir, db) = setup_test_db();
+
+        let keep = create(&db, "Keep", TaskStatus::Next);
+        let complete = create(&db, "Complete", TaskStatus::Next);
+        let defer = create(&db, "Defer", TaskStatus::Waiting);
+        let delete = create(&db, "Delete", TaskStatus::Next);
+
+        let review = db.start_review().unwrap();
+        db.record_review_decision(&review.id, &keep.id, ReviewDecision::Keep)
+            .unwrap();
+        db.record_review_decision(&review.id, &complete.id, ReviewDecision::Complete)
+            .unwrap();
+        db.record_review_decision(&review.id, &defer.id, ReviewDecision::Defer)
+            .unwrap();
+        let review = db
+            .record_review_decision(&review.id, &delete.id, ReviewDecision::Delete)
+            .unwrap();
+
+        assert_eq!(review.tasks_triaged, 4);
+        assert_eq!(review.tasks_completed, 1);
+        assert_eq!(review.tasks_deferred, 1);
+        assert_eq!(review.tasks_deleted, 1);
+
+        assert_eq!(db.get_task(&complete.id).unwrap().status, TaskStatus::Done);
+        assert_eq!(db.get_task(&defer.id).unwrap().status, TaskStatus::Someday);
+        assert!(db.get_task(&delete.id).is_err());
+
+        // A second decision on the same task changes nothing
+        assert!(db
+            .record_review_decision(&review.id, &keep.id, ReviewDecision::Complete)
+            .is_err());
+        assert_eq!(db.get_task(&keep.id).unwrap().status, TaskStatus::Next);
+        assert_eq!(db.get_review(&review.id).unwrap().tasks_triaged, 4);
+
+        // It can be triaged again in the next review
+        db.finish_review(&review.id).unwrap();
+        let next = db.start_review().unwrap();
+        db.record_review_decision(&next.id, &keep.id, ReviewDecision::Keep)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_finish_review_resets_due_date() {
+        let (_dir, db) = setup_test_db();
+
+        let before = db.get_dashboard_data().unwrap();
+        assert!(before.review_due_in_days < 0);
+
+        let review = db.start_review().unwrap();
+        let review = db.finish_review(&review.id).unwrap();
+        assert!(review.completed_at.is_some());
+        assert!(db.get_active_review().unwrap().is_none());
+
+        let after = db.get_dashboard_data().unwrap();
+        assert_eq!(after.review_due_in_days, 7);
+
+        let task = create(&db, "Late", TaskStatus::Next);
+        assert!(db
+            .record_review_decision(&review.id, &task.id, ReviewDecision::Keep)
+            .is_err());
+    }
+}
@@ -0,0 +1,195 @@
+use anyhow::Result;
+use rusqlite::params;
+
+use super::connection::Database;
+use super::models::SearchResult;
+use super::queries::TASK_COLUMNS;
+
+const DEFAULT_SEARCH_LIMIT: u32 = 50;
+
+/// Placeholders FTS5 wraps matches in, swapped for `<mark>` once the rest of
+/// the snippet is escaped. Task text has no use for these control characters.
+const MATCH_START: char = '\u{1}';
+const MATCH_END: char = '\u{2}';
+
+/// Turn free text into an FTS5 query: every word must match as a prefix.
+/// Words are quoted so FTS5 operators and punctuation in the input are
+/// treated literally.
+fn to_fts_query(text: &str) -> Option<String> {
+    let terms: Vec<String> = text
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect();
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+/// Escape a snippet for HTML and wrap its matches in `<mark>`
+fn highlight(snippet: &str) -> String {
+    let mut html = String::with_capacity(snippet.len());
+    for c in snippet.chars() {
+        match c {
+            MATCH_START => html.push_str("<mark>"),
+            MATCH_END => html.push_str("</mark>"),
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            c => html.push(c),
+        }
+    }
+    html
+}
+
+impl Database {
+    /// Full-text search over task titles and original input, best match first
+    pub fn search_tasks(&self, text: &str, limit: Option<u32>) -> Result<Vec<SearchResult>> {
+        let Some(fts_query) = to_fts_query(text) else {
+            return Ok(Vec::new());
+        };
+
+        // Title matches weigh more than matches in the raw captured input
+        let mut stmt = self.conn().prepare(&format!(
+            "SELECT {}, hits.title_snippet, hits.input_snippet, hits.rank
+             FROM tasks
+             JOIN (
+                 SELECT task_id,
+                        snippet(tasks_fts, 1, char(1), char(2), '…', 12) AS title_snippet,
+                        snippet(tasks_fts, 2, char(1), char(2), '…', 12) AS input_snippet,
+                        bm25(tasks_fts, 0.0, 10.0, 1.0) AS rank
+                 FROM tasks_fts
+                 WHERE tasks_fts MATCH ?1
+             ) hits ON hits.task_id = tasks.id
+             WHERE tasks.deleted_at IS NULL
+             ORDER BY hits.rank
+             LIMIT ?2",
+            TASK_COLUMNS
+        ))?;
+
+        let results = stmt
+            .query_map(
+                params![fts_query, limit.unwrap_or(DEFAULT_SEARCH_LIMIT)],
+                |row| {
+                    let input_snippet: Option<String> = row.get("input_snippet")?;
+                    Ok(SearchResult {
+                        task: self.row_to_task(row)?,
+                        title_snippet: highlight(&row.get::<_, String>("title_snippet")?),
+                        // Only worth showing when the match is in the input
+                        input_snippet: input_snippet
+                            .filter(|snippet| snippet.contains(MATCH_START))
+                            .map(|snippet| highlight(&snippet)),
+                        rank: row.get("rank")?,
+                    })
+                },
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{Task, TaskPatch, TaskStatus};
+    use tempfile::{tempdir, TempDir};
+
+    fn setup_test_db() -> (TempDir, Database) {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let db = Database::new(db_path).unwrap();
+        (dir, db)
+    }
+
+    fn create(db: &Database, title: &str, original_input: &str) -> Task {
+        db.create_task(
+            title.to_string(),
+            TaskStatus::Next,
+            None,
+            None,
+            Some(original_input.to_string()),
+            "test".to_string(),
+            Vec::new(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_fts_query_escaping() {
+        assert_eq!(to_fts_query("  "), None);
+        assert_eq!(
+            to_fts_query("call mom"),
+            Some("\"call\"* \"mom\"*".to_string())
+        );
+        assert_eq!(
+            to_fts_query("say \"hi\" OR"),
+            Some("\"say\"* \"\"\"hi\"\"\"* \"OR\"*".to_string())
+        );
+    }
+
+    #[test]
+    fn test_search_ranks_title_matches_first() {
+        let (_dir, db) = setup_test_db();
+
+        create(
+            &db,
+            "Email the landlord",
+            "Email the landlord about plumbing",
+        );
+        create(&db, "Fix plumbing", "Fix plumbing tomorrow");
+        create(&db, "Unrelated", "Unrelated");
+
+        let results = db.search_tasks("plumb", None).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].task.title, "Fix plumbing");
+        assert_eq!(results[0].title_snippet, "Fix <mark>plumbing</mark>");
+
+        // Operators in user input don't break the query
+        assert!(db.search_tasks("plumbing OR \"", None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_search_index_follows_edits_and_deletes() {
+        let (_dir, db) = setup_test_db();
+
+        let task = create(&db, "Renew passport", "renew passport");
+        db.update_task(
+            &task.id,
+            TaskPatch {
+                title: Some("Renew driving licence".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let results = db.search_tasks("licence", None).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].task.id, task.id);
+        // The original input has no match, so there is nothing to show from it
+        assert_eq!(results[0].input_snippet, None);
+
+        db.delete_task(&task.id).unwrap();
+        assert!(db.search_tasks("renew", None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_snippets_escape_task_text() {
+        let (_dir, db) = setup_test_db();
+        create(&db, "Fix <b>bold</b> & more", "Fix <b>bold</b> & more");
+
+        let results = db.search_tasks("bold", None).unwrap();
+        assert_eq!(
+            results[0].title_snippet,
+            "Fix &lt;b&gt;<mark>bold</mark>&lt;/b&gt; &amp; more"
+        );
+        assert_eq!(
+            results[0].input_snippet.as_deref(),
+            Some("Fix &lt;b&gt;<mark>bold</mark>&lt;/b&gt; &amp; more")
+        );
+    }
+}
@@ -0,0 +1,322 @@
+use anyhow::{bail, Result};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use rusqlite::params;
+use std::collections::{BTreeMap, HashMap};
+
+use super::connection::Database;
+use super::models::{CompletionRate, PeriodCount, Stats, StatusTime, TaskStatus};
+
+const STATUSES: [TaskStatus; 5] = [
+    TaskStatus::Now,
+    TaskStatus::Next,
+    TaskStatus::Waiting,
+    TaskStatus::Someday,
+    TaskStatus::Done,
+];
+
+impl Database {
+    /// Productivity figures for tasks between `from` (inclusive) and `to`
+    /// (exclusive). Days and weeks are in the user's timezone. Trashed
+    /// tasks are left out.
+    pub fn get_stats(&self, from: i64, to: i64) -> Result<Stats> {
+        if from >= to {
+            bail!("Stats range must end after it starts");
+        }
+
+        let completed = self.completions(from, to)?;
+        let tz = self.timezone()?;
+
+        let mut per_day: BTreeMap<NaiveDate, i64> = BTreeMap::new();
+        let mut per_week: BTreeMap<NaiveDate, i64> = BTreeMap::new();
+        for (_, completed_at) in &completed {
+            let Some(day) = DateTime::from_timestamp(*completed_at, 0)
+                .map(|dt| dt.with_timezone(&tz).date_naive())
+            else {
+                continue;
+            };
+            let week = day - Duration::days(day.weekday().num_days_from_monday() as i64);
+            *per_day.entry(day).or_default() += 1;
+            *per_week.entry(week).or_default() += 1;
+        }
+
+        let mut lead_times: Vec<i64> = completed
+            .iter()
+            .map(|(created_at, completed_at)| completed_at - created_at)
+            .collect();
+        lead_times.sort_unstable();
+
+        Ok(Stats {
+            from,
+            to,
+            completed_per_day: period_counts(per_day),
+            completed_per_week: period_counts(per_week),
+            median_lead_time_seconds: median(&lead_times),
+            time_in_status: self.time_in_status(from, to)?,
+            completion_by_context: self.completion_rates("context", from, to)?,
+            completion_by_source: self.completion_rates("source", from, to)?,
+        })
+    }
+
+    /// Helper: (created_at, completed_at) of tasks completed in the range
+    fn completions(&self, from: i64, to: i64) -> Result<Vec<(i64, i64)>> {
+        let mut stmt = self.conn().prepare(
+            "SELECT created_at, completed_at FROM tasks
+             WHERE status = 'done' AND deleted_at IS NULL
+                AND completed_at >= ?1 AND completed_at < ?2
+             ORDER BY completed_at",
+        )?;
+
+        let rows = stmt
+            .query_map(params![from, to], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(rows)
+    }
+
+    /// Helper: Seconds spent in each status within the range, replayed from
+    /// the status changes in the task history. Tasks older than the history
+    /// start in the status their first recorded change moved them out of.
+    fn time_in_status(&self, from: i64, to: i64) -> Result<Vec<StatusTime>> {
+        let mut stmt = self.conn().prepare(
+            "SELECT task_events.task_id, task_events.old_value, task_events.new_value,
+                    task_events.created_at
+             FROM task_events JOIN tasks ON tasks.id = task_events.task_id
+             WHERE task_events.field = 'status' AND tasks.deleted_at IS NULL
+                AND tasks.created_at < ?1
+             ORDER BY task_events.id",
+        )?;
+        let mut changes: HashMap<String, Vec<(Option<String>, String, i64)>> = HashMap::new();
+        for row in stmt.query_map(params![to], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+        })? {
+            let (task_id, old, new, at): (String, _, _, _) = row?;
+            changes.entry(task_id).or_default().push((old, new, at));
+        }
+
+        let mut stmt = self.conn().prepare(
+            "SELECT id, status, created_at FROM tasks
+             WHERE deleted_at IS NULL AND created_at < ?1",
+        )?;
+        let tasks = stmt
+            .query_map(params![to], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i64>(2)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let end = to.min(Utc::now().timestamp());
+        let mut seconds: HashMap<String, i64> = HashMap::new();
+        let mut add = |status: &str, start: i64, stop: i64| {
+            let overlap = stop.min(end) - start.max(from);
+            if overlap > 0 {
+                *seconds.entry(status.to_string()).or_default() += overlap;
+            }
+        };
+
+        for (id, current, created_at) in tasks {
+            let changes = changes.remove(&id).unwrap_or_default();
+            let mut status = changes
+                .first()
+                .and_then(|(old, _, _)| old.clone())
+                .unwrap_or(current);
+            let mut since = created_at;
+            for (_, new, at) in changes {
+                add(&status, since, at);
+                status = new;
+                since = at;
+            }
+            add(&status, since, end);
+        }
+
+        Ok(STATUSES
+            .into_iter()
+            .map(|status| StatusTime {
+                seconds: seconds.get(status.as_str()).copied().unwrap_or(0),
+                status,
+            })
+            .collect())
+    }
+
+    /// Helper: Share of the tasks captured in the range that are done, per
+    /// value of `column`, busiest first
+    fn completion_rates(&self, column: &str, from: i64, to: i64) -> Result<Vec<CompletionRate>> {
+        let mut stmt = self.conn().prepare(&format!(
+            "SELECT {0}, COUNT(*), SUM(status = 'done') FROM tasks
+             WHERE deleted_at IS NULL AND created_at >= ?1 AND created_at < ?2
+             GROUP BY {0} COLLATE NOCASE
+             ORDER BY COUNT(*) DESC, {0} COLLATE NOCASE",
+            column
+        ))?;
+
+        let rates = stmt
+            .query_map(params![from, to], |row| {
+                let created: i64 = row.get(1)?;
+                let completed: i64 = row.get(2)?;
+                Ok(CompletionRate {
+                    name: row.get(0)?,
+                    created,
+                    completed,
+                    rate: completed as f64 / created as f64,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(rates)
+    }
+}
+
+fn period_counts(counts: BTreeMap<NaiveDate, i64>) -> Vec<PeriodCount> {
+    counts
+        .into_iter()
+        .map(|(start, completed)| PeriodCount {
+            start: start.format("%Y-%m-%d").to_string(),
+            completed,
+        })
+        .collect()
+}
+
+/// Median of sorted values
+fn median(sorted: &[i64]) -> Option<i64> {
+    let mid = sorted.len() / 2;
+    match sorted.len() {
+        0 => None,
+        len if len % 2 == 1 => Some(sorted[mid]),
+        _ => Some((sorted[mid - 1] + sorted[mid]) / 2),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::NewTask;
+    use tempfile::{tempdir, TempDir};
+
+    fn setup_test_db() -> (TempDir, Database) {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let db = Database::new(db_path).unwrap();
+        db.set_timezone("UTC").unwrap();
+        (dir, db)
+    }
+
+    /// A task created at `created_at`, completed at `completed_at` if given
+    fn task(db: &Database, context: &str, created_at: i64, completed_at: Option<i64>) {
+        let task = db
+            .insert_task(&NewTask {
+                title: "Task".to_string(),
+                status: TaskStatus::Next,
+                context: Some(context.to_string()),
+                source: "test".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        db.conn()
+            .execute(
+                "UPDATE tasks SET created_at = ?1 WHERE id = ?2",
+                params![created_at, task.id],
+            )
+            .unwrap();
+        if let Some(completed_at) = completed_at {
+            db.conn()
+                .execute(
+                    "UPDATE tasks SET status = 'done', completed_at = ?1 WHERE id = ?2",
+                    params![completed_at, task.id],
+                )
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_completions() {
+        let (_dir, db) = setup_test_db();
+        // Monday 2024-01-01 00:00 UTC
+        let monday = 1_704_067_200;
+        let day = 86_400;
+
+        task(&db, "work", monday, Some(monday + 3_600));
+        task(&db, "Work", monday, Some(monday + day + 7_200));
+        task(&db, "home", monday, Some(monday + 8 * day));
+        task(&db, "home", monday + day, None);
+        task(&db, "work", monday - 10 * day, Some(monday - 9 * day));
+
+        let stats = db.get_stats(monday, monday + 14 * day).unwrap();
+
+        let days: Vec<_> = stats
+            .completed_per_day
+            .iter()
+            .map(|p| (p.start.as_str(), p.completed))
+            .collect();
+        assert_eq!(
+            days,
+            vec![("2024-01-01", 1), ("2024-01-02", 1), ("2024-01-09", 1)]
+        );
+        let weeks: Vec<_> = stats
+            .completed_per_week
+            .iter()
+            .map(|p| (p.start.as_str(), p.completed))
+            .collect();
+        assert_eq!(weeks, vec![("2024-01-01", 2), ("2024-01-08", 1)]);
+
+        assert_eq!(stats.median_lead_time_seconds, Some(day + 7_200));
+
+        // Rates count tasks captured in the range, contexts ignoring case
+        assert_eq!(stats.completion_by_context.len(), 2);
+        let home = &stats.completion_by_context[0];
+        assert_eq!(home.name.as_deref(), Some("home"));
+        assert_eq!((home.created, home.completed), (2, 1));
+        assert_eq!(home.rate, 0.5);
+        assert_eq!(stats.completion_by_source[0].created, 4);
+
+        assert!(db.get_stats(monday, monday).is_err());
+    }
+
+    #[test]
+    fn test_time_in_status() {
+        let (_dir, db) = setup_test_db();
+        let task = db
+            .insert_task(&NewTask {
+                title: "Chase invoice".to_string(),
+                status: TaskStatus::Next,
+                source: "test".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        db.update_task_status(&task.id, TaskStatus::Waiting)
+            .unwrap();
+
+        // Spread the history out: created 10h ago, waiting for the last 4h
+        let now = Utc::now().timestamp();
+        db.conn()
+            .execute(
+                "UPDATE tasks SET created_at = ?1 WHERE id = ?2",
+                params![now - 36_000, task.id],
+            )
+            .unwrap();
+        db.conn()
+            .execute(
+                "UPDATE task_events SET created_at = ?1 WHERE field = 'status'",
+                params![now - 14_400],
+            )
+            .unwrap();
+
+        let stats = db.get_stats(now - 86_400, now + 60).unwrap();
+        let seconds = |status: TaskStatus| {
+            stats
+                .time_in_status
+                .iter()
+                .find(|time| time.status == status)
+                .unwrap()
+                .seconds
+        };
+        assert_eq!(seconds(TaskStatus::Next), 21_600);
+        assert!((14_400..14_410).contains(&seconds(TaskStatus::Waiting)));
+        assert_eq!(seconds(TaskStatus::Done), 0);
+
+        // Clipped to the range
+        let stats = db.get_stats(now - 18_000, now - 14_400).unwrap();
+        assert_eq!(stats.time_in_status[1].seconds, 3_600);
+    }
+}
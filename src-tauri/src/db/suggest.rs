@@ -0,0 +1,296 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use rusqlite::params;
+
+use super::connection::Database;
+use super::models::{Priority, ScoreFactor, ScoreReason, Suggestion, Task};
+use super::queries::TASK_COLUMNS;
+
+/// Most points the age of a task can add
+const MAX_AGE_POINTS: f64 = 15.0;
+
+impl Database {
+    /// Rank NEXT tasks as candidates for NOW, best first. In context mode,
+    /// tasks needing another context are left out.
+    pub fn suggest_now_tasks(&self, limit: usize) -> Result<Vec<Suggestion>> {
+        let now = Utc::now().with_timezone(&self.timezone()?);
+        let current_context = self.current_context()?;
+
+        let mut stmt = self.conn().prepare(&format!(
+            "SELECT {} FROM tasks
+             WHERE status = 'next' AND deleted_at IS NULL
+                AND (?1 IS NULL OR context = ?1 COLLATE NOCASE OR context IS NULL)
+             ORDER BY created_at ASC",
+            TASK_COLUMNS
+        ))?;
+        let tasks = stmt
+            .query_map(params![current_context], |row| self.row_to_task(row))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Stable sort: equal scores keep the oldest task first
+        let mut suggestions: Vec<_> = tasks
+            .into_iter()
+            .map(|task| score_task(task, &now, current_context.as_deref()))
+            .collect();
+        suggestions.sort_by(|a, b| b.score.total_cmp(&a.score));
+        suggestions.truncate(limit);
+
+        Ok(suggestions)
+    }
+}
+
+/// Score a task for doing right now. Each factor that applies adds a reason.
+pub(crate) fn score_task(
+    task: Task,
+    now: &DateTime<Tz>,
+    current_context: Option<&str>,
+) -> Suggestion {
+    let mut reasons = Vec::new();
+    let mut add = |factor, points: f64, reason: String| {
+        reasons.push(ScoreReason {
+            factor,
+            points,
+            reason,
+        })
+    };
+
+    // Scheduled: overdue and due-today tasks first, far-off ones later
+    if let Some(scheduled) = task
+        .scheduled_for
+        .and_then(|ts| DateTime::from_timestamp(ts, 0))
+    {
+        let scheduled = scheduled.with_timezone(&now.timezone());
+        let days = (scheduled.date_naive() - now.date_naive()).num_days();
+        match days {
+            ..=-1 => add(
+                ScoreFactor::Schedule,
+                30.0 + (-days).min(10) as f64,
+                format!("Overdue by {} day{}", -days, plural(-days)),
+            ),
+            0 if scheduled <= *now => add(
+                ScoreFactor::Schedule,
+                30.0,
+                "Scheduled for earlier today".to_string(),
+            ),
+            0 => add(ScoreFactor::Schedule, 25.0, "Due today".to_string()),
+            1..=3 => add(
+                ScoreFactor::Schedule,
+                15.0 - 3.0 * days as f64,
+                format!("Due in {} day{}", days, plural(days)),
+            ),
+            8.. => add(
+                ScoreFactor::Schedule,
+                -10.0,
+                format!("Not due for {} days", days),
+            ),
+            _ => {}
+        }
+    }
+
+    // Age: half a point per day in the list, so nothing sits forever
+    let age_days = (now.timestamp() - task.created_at) / 86400;
+    if age_days >= 1 {
+        add(
+            ScoreFactor::Age,
+            (age_days as f64 * 0.5).min(MAX_AGE_POINTS),
+            format!("Captured {} day{} ago", age_days, plural(age_days)),
+        );
+    }
+
+    // Tasks for other contexts never get here: suggest_now_tasks filters them out
+    if let (Some(current), Some(context)) = (current_context, task.context.as_deref()) {
+        if current.eq_ignore_ascii_case(context) {
+            add(ScoreFactor::Context, 20.0, format!("Matches @{}", context));
+        }
+    }
+
+    match task.priority {
+        Some(Priority::High) => add(ScoreFactor::Priority, 25.0, "High priority".to_string()),
+        Some(Priority::Medium) => add(ScoreFactor::Priority, 12.0, "Medium priority".to_string()),
+        Some(Priority::Low) => add(ScoreFactor::Priority, -5.0, "Low priority".to_string()),
+        None => {}
+    }
+
+    match task.effort_minutes {
+        Some(minutes @ ..=15) => add(
+            ScoreFactor::Effort,
+            10.0,
+            format!("Quick win ({} min)", minutes),
+        ),
+        Some(minutes @ 16..=60) => add(
+            ScoreFactor::Effort,
+            5.0,
+            format!("Fits in an hour ({} min)", minutes),
+        ),
+        Some(minutes @ 121..) => add(
+            ScoreFactor::Effort,
+            -5.0,
+            format!("Long task ({} min)", minutes),
+        ),
+        _ => {}
+    }
+
+    let score = reasons.iter().map(|reason| reason.points).sum();
+    Suggestion {
+        task,
+        score,
+        reasons,
+    }
+}
+
+fn plural(n: i64) -> &'static str {
+    if n == 1 {
+        ""
+    } else {
+        "s"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::{NewTask, TaskStatus};
+    use chrono::TimeZone;
+    use tempfile::{tempdir, TempDir};
+
+    fn setup_test_db() -> (TempDir, Database) {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let db = Database::new(db_path).unwrap();
+        (dir, db)
+    }
+
+    fn task(created_at: i64) -> Task {
+        Task {
+            id: "t".to_string(),
+            title: "Task".to_string(),
+            status: TaskStatus::Next,
+            context: None,
+            scheduled_for: None,
+            completed_at: None,
+            created_at,
+            updated_at: created_at,
+            original_input: None,
+            source: "test".to_string(),
+            tags: Vec::new(),
+            sync_version: 1,
+            recurrence: None,
+            waiting_on: None,
+            waiting_since: None,
+            follow_up_at: None,
+            priority: None,
+            effort_minutes: None,
+            energy: None,
+            project_id: None,
+            parent_id: None,
+            deleted_at: None,
+        }
+    }
+
+    fn factors(suggestion: &Suggestion) -> Vec<(ScoreFactor, f64)> {
+        suggestion
+            .reasons
+            .iter()
+            .map(|r| (r.factor, r.points))
+            .collect()
+    }
+
+    #[test]
+    fn test_score_factors() {
+        let now = chrono_tz::Europe::Berlin
+            .with_ymd_and_hms(2026, 5, 6, 14, 0, 0)
+            .unwrap();
+        let day = 86400;
+
+        // Brand new with nothing set: no reasons, no score
+        let s = score_task(task(now.timestamp()), &now, None);
+        assert!(s.reasons.is_empty());
+        assert_eq!(s.score, 0.0);
+
+        let mut t = task(now.timestamp() - 10 * day);
+        t.scheduled_for = Some(now.timestamp() - 2 * day);
+        t.context = Some("office".to_string());
+        t.priority = Some(Priority::High);
+        t.effort_minutes = Some(10);
+        let s = score_task(t, &now, Some("Office"));
+        assert_eq!(
+            factors(&s),
+            vec![
+                (ScoreFactor::Schedule, 32.0),
+                (ScoreFactor::Age, 5.0),
+                (ScoreFactor::Context, 20.0),
+                (ScoreFactor::Priority, 25.0),
+                (ScoreFactor::Effort, 10.0),
+            ]
+        );
+        assert_eq!(s.score, 92.0);
+        assert_eq!(s.reasons[0].reason, "Overdue by 2 days");
+
+        // A far-off date, long and low priority all count against
+        let mut t = task(now.timestamp() - 100 * day);
+        t.scheduled_for = Some(now.timestamp() + 30 * day);
+        t.priority = Some(Priority::Low);
+        t.effort_minutes = Some(240);
+        let s = score_task(t, &now, Some("office"));
+        assert_eq!(
+            factors(&s),
+            vec![
+                (ScoreFactor::Schedule, -10.0),
+                (ScoreFactor::Age, MAX_AGE_POINTS),
+                (ScoreFactor::Priority, -5.0),
+                (ScoreFactor::Effort, -5.0),
+            ]
+        );
+
+        // Later today vs. already past today
+        let mut t = task(now.timestamp());
+        t.scheduled_for = Some(now.timestamp() + 3600);
+        assert_eq!(score_task(t.clone(), &now, None).score, 25.0);
+        t.scheduled_for = Some(now.timestamp() - 3600);
+        assert_eq!(score_task(t, &now, None).score, 30.0);
+    }
+
+    #[test]
+    fn test_suggest_now_tasks() {
+        let (_dir, db) = setup_test_db();
+
+        let insert = |title: &str, context: Option<&str>, priority| {
+            db.insert_task(&NewTask {
+                title: title.to_string(),
+                context: context.map(str::to_string),
+                source: "test".to_string(),
+                priority,
+                ..Default::default()
+            })
+            .unwrap()
+        };
+        insert("Plain", None, None);
+        insert("Errand", Some("town"), Some(Priority::Medium));
+        insert("Urgent call", Some("phone"), Some(Priority::High));
+        db.insert_task(&NewTask {
+            title: "Already now".to_string(),
+            status: TaskStatus::Now,
+            source: "test".to_string(),
+            priority: Some(Priority::High),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let titles = |suggestions: Vec<Suggestion>| -> Vec<String> {
+            suggestions.into_iter().map(|s| s.task.title).collect()
+        };
+
+        assert_eq!(
+            titles(db.suggest_now_tasks(10).unwrap()),
+            vec!["Urgent call", "Errand", "Plain"]
+        );
+
+        // In town, the call that needs a phone is left out
+        db.set_current_context(Some("@Town")).unwrap();
+        assert_eq!(
+            titles(db.suggest_now_tasks(10).unwrap()),
+            vec!["Errand", "Plain"]
+        );
+    }
+}
@@ -0,0 +1,220 @@
+use anyhow::Result;
+use chrono::Utc;
+use rusqlite::{params, Row};
+use uuid::Uuid;
+
+use super::connection::Database;
+use super::models::{Energy, Priority, SyncLog, Task, TaskStatus};
+use super::queries::TASK_COLUMNS;
+use super::recurrence::Recurrence;
+
+/// Most rows kept in the sync log; a background sync adds a few every tick
+const SYNC_LOG_LIMIT: i64 = 1000;
+
+impl Database {
+    /// Get every task in the database, trashed ones included, oldest first
+    pub fn get_all_tasks(&self) -> Result<Vec<Task>> {
+        let mut stmt = self.conn().prepare(&format!(
+            "SELECT {} FROM tasks ORDER BY created_at ASC",
+            TASK_COLUMNS
+        ))?;
+
+        let tasks = stmt
+            .query_map([], |row| self.row_to_task(row))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(tasks)
+    }
+
+    /// Write a task exactly as given, including its timestamps and sync_version.
+    /// Used by the sync engine to apply remote changes.
+    pub fn upsert_task(&self, task: &Task) -> Result<()> {
+        if task.status == TaskStatus::Now && task.deleted_at.is_none() {
+            self.demote_now_task(Some(&task.id))?;
+        }
+
+        self.conn().execute(
+            "INSERT INTO tasks (id, title, status, context, scheduled_for, completed_at, created_at, updated_at, original_input, source, sync_version, recurrence, waiting_on, waiting_since, follow_up_at, priority, effort_minutes, energy, project_id, parent_id, deleted_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21)
+             ON CONFLICT(id) DO UPDATE SET
+                title = excluded.title,
+                status = excluded.status,
+                context = excluded.context,
+                scheduled_for = excluded.scheduled_for,
+                completed_at = excluded.completed_at,
+                created_at = excluded.created_at,
+                updated_at = excluded.updated_at,
+                original_input = excluded.original_input,
+                source = excluded.source,
+                sync_version = excluded.sync_version,
+                recurrence = excluded.recurrence,
+                waiting_on = excluded.waiting_on,
+                waiting_since = excluded.waiting_since,
+                follow_up_at = excluded.follow_up_at,
+                priority = excluded.priority,
+                effort_minutes = excluded.effort_minutes,
+                energy = excluded.energy,
+                project_id = excluded.project_id,
+                parent_id = excluded.parent_id,
+                deleted_at = excluded.deleted_at",
+            params![
+                task.id,
+                task.title,
+                task.status.as_str(),
+                task.context,
+                task.scheduled_for,
+                task.completed_at,
+                task.created_at,
+                task.updated_at,
+                task.original_input,
+                task.source,
+                task.sync_version,
+                task.recurrence.as_ref().map(Recurrence::to_rrule),
+                task.waiting_on,
+                task.waiting_since,
+                task.follow_up_at,
+                task.priority.map(Priority::as_i32),
+                task.effort_minutes,
+                task.energy.as_ref().map(Energy::as_str),
+                task.project_id,
+                task.parent_id,
+                task.deleted_at,
+            ],
+        )?;
+
+        self.set_task_tags(&task.id, &task.tags)
+    }
+
+    /// Permanently delete a task, whether or not it is in the trash.
+    /// Used by the sync engine when a task was purged on another device.
+    pub fn remove_task(&self, id: &str) -> Result<()> {
+        self.conn()
+            .execute("DELETE FROM tasks WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    /// Record a sync operation, dropping the oldest rows past the limit
+    pub fn log_sync(
+        &self,
+        operation: &str,
+        status: &str,
+        message: Option<&str>,
+    ) -> Result<SyncLog> {
+        let log = SyncLog {
+            id: Uuid::new_v4().to_string(),
+            timestamp: Utc::now().timestamp(),
+            operation: operation.to_string(),
+            status: status.to_string(),
+            message: message.map(|m| m.to_string()),
+        };
+
+        self.conn().execute(
+            "INSERT INTO sync_log (id, timestamp, operation, status, message) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![log.id, log.timestamp, log.operation, log.status, log.message],
+        )?;
+        self.conn().execute(
+            "DELETE FROM sync_log WHERE rowid <= (SELECT MAX(rowid) FROM sync_log) - ?1",
+            params![SYNC_LOG_LIMIT],
+        )?;
+
+        Ok(log)
+    }
+
+    /// Get the most recent sync operations, newest first
+    pub fn get_sync_log(&self, limit: u32) -> Result<Vec<SyncLog>> {
+        let mut stmt = self.conn().prepare(
+            "SELECT id, timestamp, operation, status, message
+             FROM sync_log ORDER BY timestamp DESC, rowid DESC LIMIT ?1",
+        )?;
+
+        let logs = stmt
+            .query_map(params![limit], row_to_sync_log)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(logs)
+    }
+}
+
+/// Helper: Convert database row to SyncLog
+fn row_to_sync_log(row: &Row) -> rusqlite::Result<SyncLog> {
+    Ok(SyncLog {
+        id: row.get(0)?,
+        timestamp: row.get(1)?,
+        operation: row.get(2)?,
+        status: row.get(3)?,
+        message: row.get(4)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_synced_tasks_keep_their_updated_at() {
+        let dir = tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db")).unwrap();
+        let task = db
+            .insert_task(&crate::db::NewTask {
+                title: "Task".to_string(),
+                source: "test".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        db.conn()
+            .execute(
+                "UPDATE tasks SET updated_at = 100 WHERE id = ?1",
+                params![task.id],
+            )
+            .unwrap();
+
+        // A local edit that leaves updated_at alone gets stamped
+        db.update_task_status(&task.id, TaskStatus::Waiting)
+            .unwrap();
+        let edited = db.get_task(&task.id).unwrap();
+        assert!(edited.updated_at > 100);
+
+        // A synced copy keeps the timestamp it was written with
+        let remote = Task {
+            title: "Remote title".to_string(),
+            updated_at: 50,
+            sync_version: edited.sync_version + 1,
+            ..edited
+        };
+        db.upsert_task(&remote).unwrap();
+        let merged = db.get_task(&task.id).unwrap();
+        assert_eq!(merged.title, "Remote title");
+        assert_eq!(merged.updated_at, 50);
+    }
+
+    #[test]
+    fn test_sync_log_is_capped() {
+        let dir = tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db")).unwrap();
+
+        for i in 0..SYNC_LOG_LIMIT + 5 {
+            db.log_sync("pull", "success", Some(&i.to_string()))
+                .unwrap();
+        }
+
+        let count: i64 = db
+            .conn()
+            .query_row("SELECT COUNT(*) FROM sync_log", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, SYNC_LOG_LIMIT);
+
+        let newest = db.get_sync_log(1).unwrap();
+        let expected = (SYNC_LOG_LIMIT + 4).to_string();
+        assert_eq!(newest[0].message.as_deref(), Some(expected.as_str()));
+        let oldest: String = db
+            .conn()
+            .query_row(
+                "SELECT message FROM sync_log ORDER BY rowid LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(oldest, "5");
+    }
+}
@@ -0,0 +1,287 @@
+use anyhow::{bail, Result};
+use chrono::Utc;
+use rusqlite::params;
+
+use super::connection::Database;
+use super::models::{TagCount, Task};
+use super::queries::TASK_COLUMNS;
+
+/// Normalize a tag name: strip a leading '#', lowercase, and allow only
+/// letters, digits, '_' and '-'
+pub fn normalize_tag(tag: &str) -> Result<String> {
+    let tag = tag.trim().trim_start_matches('#').to_lowercase();
+
+    if tag.is_empty() {
+        bail!("Tag cannot be empty");
+    }
+    if !tag
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+    {
+        bail!("Invalid tag: {:?}", tag);
+    }
+
+    Ok(tag)
+}
+
+/// Normalize a list of tags, dropping duplicates while keeping order
+pub fn normalize_tags(tags: &[String]) -> Result<Vec<String>> {
+    let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let tag = normalize_tag(tag)?;
+        if !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    Ok(normalized)
+}
+
+impl Database {
+    /// Replace the full set of tags on a task
+    pub fn set_task_tags(&self, task_id: &str, tags: &[String]) -> Result<()> {
+        let tags = normalize_tags(tags)?;
+
+        // Only touch links that change, so the history shows real edits
+        self.conn().execute(
+            "DELETE FROM task_tags
+             WHERE task_id = ?1
+                AND tag_id NOT IN (
+                    SELECT tags.id FROM tags JOIN json_each(?2) ON tags.name = json_each.value
+                )",
+            params![task_id, serde_json::to_string(&tags)?],
+        )?;
+
+        for tag in &tags {
+            self.link_tag(task_id, tag)?;
+        }
+
+        self.purge_unused_tags()
+    }
+
+    /// Add a tag to a task and return the updated task
+    pub fn add_task_tag(&self, task_id: &str, tag: &str) -> Result<Task> {
+        let tag = normalize_tag(tag)?;
+        self.get_task(task_id)?;
+
+        if self.link_tag(task_id, &tag)? {
+            self.bump_sync_version(task_id)?;
+        }
+
+        self.get_task(task_id)
+    }
+
+    /// Remove a tag from a task and return the updated task
+    pub fn remove_task_tag(&self, task_id: &str, tag: &str) -> Result<Task> {
+        let tag = normalize_tag(tag)?;
+        self.get_task(task_id)?;
+
+        let removed = self.conn().execute(
+            "DELETE FROM task_tags
+             WHERE task_id = ?1 AND tag_id = (SELECT id FROM tags WHERE name = ?2)",
+            params![task_id, tag],
+        )?;
+
+        if removed > 0 {
+            self.bump_sync_version(task_id)?;
+            self.purge_unused_tags()?;
+        }
+
+        self.get_task(task_id)
+    }
+
+    /// List every tag in use with its task counts, most used first
+    pub fn list_tags(&self) -> Result<Vec<TagCount>> {
+        let mut stmt = self.conn().prepare(
+            "SELECT tags.name,
+                    COUNT(task_tags.task_id),
+                    COUNT(CASE WHEN t.status != 'done' THEN 1 END)
+             FROM tags
+             JOIN task_tags ON task_tags.tag_id = tags.id
+             JOIN tasks t ON t.id = task_tags.task_id
+             WHERE t.deleted_at IS NULL
+             GROUP BY tags.id
+             ORDER BY COUNT(task_tags.task_id) DESC, tags.name ASC",
+        )?;
+
+        let tags = stmt
+            .query_map([], |row| {
+                Ok(TagCount {
+                    name: row.get(0)?,
+                    task_count: row.get(1)?,
+                    open_task_count: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(tags)
+    }
+
+    /// Get all tasks carrying a tag, newest first
+    pub fn get_tasks_by_tag(&self, tag: &str) -> Result<Vec<Task>> {
+        let tag = normalize_tag(tag)?;
+
+        let mut stmt = self.conn().prepare(&format!(
+            "SELECT {} FROM tasks
+             WHERE id IN (
+                 SELECT task_tags.task_id FROM task_tags
+                 JOIN tags ON tags.id = task_tags.tag_id
+                 WHERE tags.name = ?1
+             )
+             AND deleted_at IS NULL
+             ORDER BY created_at DESC",
+            TASK_COLUMNS
+        ))?;
+
+        let tasks = stmt
+            .query_map(params![tag], |row| self.row_to_task(row))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(tasks)
+    }
+
+    /// Helper: Attach an already-normalized tag, creating it if needed.
+    /// Returns false when the task already had the tag.
+    fn link_tag(&self, task_id: &str, tag: &str) -> Result<bool> {
+        self.conn().execute(
+            "INSERT OR IGNORE INTO tags (name, created_at) VALUES (?1, ?2)",
+            params![tag, Utc::now().timestamp()],
+        )?;
+
+        let inserted = self.conn().execute(
+            "INSERT OR IGNORE INTO task_tags (task_id, tag_id)
+             SELECT ?1, id FROM tags WHERE name = ?2",
+            params![task_id, tag],
+        )?;
+
+        Ok(inserted > 0)
+    }
+
+    /// Helper: Drop tags no task uses anymore
+    fn purge_unused_tags(&self) -> Result<()> {
+        self.conn().execute(
+            "DELETE FROM tags WHERE id NOT IN (SELECT tag_id FROM task_tags)",
+            [],
+        )?;
+        Ok(())
+    }
+
+    /// Helper: Record a change to a task that didn't touch the tasks row itself
+    fn bump_sync_version(&self, task_id: &str) -> Result<()> {
+        self.conn().execute(
+            "UPDATE tasks SET sync_version = sync_version + 1 WHERE id = ?1",
+            params![task_id],
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::TaskStatus;
+    use tempfile::{tempdir, TempDir};
+
+    fn setup_test_db() -> (TempDir, Database) {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let db = Database::new(db_path).unwrap();
+        (dir, db)
+    }
+
+    fn create(db: &Database, title: &str, status: TaskStatus, tags: &[&str]) -> Task {
+        db.create_task(
+            title.to_string(),
+            status,
+            None,
+            None,
+            None,
+            "test".to_string(),
+            tags.iter().map(|t| t.to_string()).collect(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_normalize_tag() {
+        assert_eq!(normalize_tag("#Work").unwrap(), "work");
+        assert_eq!(normalize_tag(" low-energy ").unwrap(), "low-energy");
+        assert!(normalize_tag("#").is_err());
+        assert!(normalize_tag("two words").is_err());
+        assert!(normalize_tag("a,b").is_err());
+    }
+
+    #[test]
+    fn test_create_task_with_tags() {
+        let (_dir, db) = setup_test_db();
+
+        let task = create(
+            &db,
+            "Tagged",
+            TaskStatus::Next,
+            &["work", "#Urgent", "work"],
+        );
+
+        assert_eq!(task.tags, vec!["urgent", "work"]);
+    }
+
+    #[test]
+    fn test_add_and_remove_tags() {
+        let (_dir, db) = setup_test_db();
+
+        let task = create(&db, "Task", TaskStatus::Next, &[]);
+
+        let tagged = db.add_task_tag(&task.id, "home").unwrap();
+        assert_eq!(tagged.tags, vec!["home"]);
+        assert_eq!(tagged.sync_version, task.sync_version + 1);
+
+        // Adding the same tag twice is a no-op
+        let again = db.add_task_tag(&task.id, "#home").unwrap();
+        assert_eq!(again.sync_version, tagged.sync_version);
+
+        let untagged = db.remove_task_tag(&task.id, "home").unwrap();
+        assert!(untagged.tags.is_empty());
+        assert!(db.list_tags().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_list_tags_and_tasks_by_tag() {
+        let (_dir, db) = setup_test_db();
+
+        create(&db, "One", TaskStatus::Next, &["work"]);
+        create(&db, "Two", TaskStatus::Done, &["work", "home"]);
+        create(&db, "Three", TaskStatus::Next, &["home"]);
+        create(&db, "Four", TaskStatus::Next, &["work"]);
+
+        let tags = db.list_tags().unwrap();
+        assert_eq!(
+            tags[0],
+            TagCount {
+                name: "work".to_string(),
+                task_count: 3,
+                open_task_count: 2,
+            }
+        );
+        assert_eq!(tags[1].name, "home");
+
+        let work = db.get_tasks_by_tag("#work").unwrap();
+        assert_eq!(work.len(), 3);
+    }
+
+    #[test]
+    fn test_tags_removed_with_task() {
+        let (_dir, db) = setup_test_db();
+
+        let task = create(&db, "Gone", TaskStatus::Next, &["temp"]);
+        db.delete_task(&task.id).unwrap();
+
+        // Trashed tasks keep their tags for a restore but aren't counted
+        assert!(db.list_tags().unwrap().is_empty());
+        db.purge_task(&task.id).unwrap();
+
+        let links: i64 = db
+            .conn()
+            .query_row("SELECT COUNT(*) FROM task_tags", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(links, 0);
+    }
+}
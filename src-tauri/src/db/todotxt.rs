@@ -0,0 +1,374 @@
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use chrono_tz::Tz;
+use rusqlite::params;
+use std::collections::HashMap;
+
+use super::connection::Database;
+use super::contexts::normalize_context;
+use super::models::{ImportReport, NewTask, Priority, SkippedLine, Task, TaskStatus, TodoTxtTask};
+use super::queries::TASK_COLUMNS;
+use super::recurrence::local_datetime;
+use super::tags::normalize_tag;
+
+/// Hour a bare `due:` date lands on, the same as a bare date in quick capture
+const DUE_HOUR: u32 = 9;
+
+impl Database {
+    /// Every task outside the trash as todo.txt, open tasks first
+    pub fn export_todotxt(&self) -> Result<String> {
+        let tz = self.timezone()?;
+        let projects = self.project_titles()?;
+
+        let mut out = String::new();
+        for task in self.live_tasks()? {
+            out.push_str(&todotxt_line(&task, &projects, &tz));
+            out.push('\n');
+        }
+
+        Ok(out)
+    }
+
+    /// Read todo.txt lines into tasks. With `dry_run` nothing is written and
+    /// the report shows what would be created; otherwise the tasks and any
+    /// missing projects are created in one transaction.
+    pub fn import_todotxt(&self, text: &str, dry_run: bool) -> Result<ImportReport> {
+        let tz = self.timezone()?;
+        let mut report = ImportReport {
+            dry_run,
+            ..Default::default()
+        };
+
+        for (index, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match parse_todotxt_line(line, &tz) {
+                Ok(mut task) => {
+                    task.line = index + 1;
+                    report.tasks.push(task);
+                }
+                Err(reason) => report.skipped.push(SkippedLine {
+                    line: index + 1,
+                    text: line.to_string(),
+                    reason,
+                }),
+            }
+        }
+
+        // todo.txt projects are single words; match ours with spaces as dashes
+        let mut project_ids: HashMap<String, String> = self
+            .get_all_projects()?
+            .into_iter()
+            .map(|project| (project_key(&project.title), project.id))
+            .collect();
+        for name in report.tasks.iter().filter_map(|task| task.project.as_ref()) {
+            if !project_ids.contains_key(&project_key(name)) && !report.new_projects.contains(name)
+            {
+                report.new_projects.push(name.clone());
+            }
+        }
+
+        if dry_run {
+            return Ok(report);
+        }
+
+        let tx = self.conn().unchecked_transaction()?;
+
+        for name in &report.new_projects {
+            let project = self.create_project(name, None)?;
+            project_ids.insert(project_key(name), project.id);
+        }
+
+        let now = Utc::now().timestamp();
+        for item in &report.tasks {
+            let task = self.insert_task(&NewTask {
+                title: item.title.clone(),
+                status: item.status.clone(),
+                context: item.context.clone(),
+                scheduled_for: item.scheduled_for,
+                original_input: Some(item.original.clone()),
+                source: "todotxt".to_string(),
+                tags: item.tags.clone(),
+                priority: item.priority,
+                project_id: item
+                    .project
+                    .as_ref()
+                    .and_then(|name| project_ids.get(&project_key(name)).cloned()),
+                ..Default::default()
+            })?;
+
+            let completed_at =
+                (item.status == TaskStatus::Done).then(|| item.completed_at.unwrap_or(now));
+            self.conn().execute(
+                "UPDATE tasks SET created_at = COALESCE(?1, created_at), completed_at = ?2
+                 WHERE id = ?3",
+                params![item.created_at, completed_at, task.id],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(report)
+    }
+
+    /// Helper: Tasks outside the trash, open ones first, oldest first
+    pub(crate) fn live_tasks(&self) -> Result<Vec<Task>> {
+        let mut stmt = self.conn().prepare(&format!(
+            "SELECT {} FROM tasks WHERE deleted_at IS NULL
+             ORDER BY status = 'done', created_at ASC",
+            TASK_COLUMNS
+        ))?;
+
+        let tasks = stmt
+            .query_map([], |row| self.row_to_task(row))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(tasks)
+    }
+
+    /// Helper: Project titles by ID
+    pub(crate) fn project_titles(&self) -> Result<HashMap<String, String>> {
+        Ok(self
+            .get_all_projects()?
+            .into_iter()
+            .map(|project| (project.id, project.title))
+            .collect())
+    }
+}
+
+/// One task as a todo.txt line. Tags are written as `#tag`; statuses other
+/// than NEXT and DONE as `status:`; done tasks keep their priority as `pri:`.
+fn todotxt_line(task: &Task, projects: &HashMap<String, String>, tz: &Tz) -> String {
+    let mut parts: Vec<String> = Vec::new();
+
+    if task.status == TaskStatus::Done {
+        parts.push("x".to_string());
+        if let Some(completed_at) = task.completed_at {
+            parts.push(format_date(completed_at, tz));
+        }
+    } else if let Some(priority) = task.priority {
+        parts.push(format!("({})", priority_letter(priority)));
+    }
+    parts.push(format_date(task.created_at, tz));
+    parts.push(task.title.replace('\n', " "));
+
+    if let Some(title) = task.project_id.as_ref().and_then(|id| projects.get(id)) {
+        parts.push(format!("+{}", project_key(title)));
+    }
+    if let Some(context) = &task.context {
+        parts.push(format!("@{}", context));
+    }
+    parts.extend(task.tags.iter().map(|tag| format!("#{}", tag)));
+    if let Some(scheduled_for) = task.scheduled_for {
+        parts.push(format!("due:{}", format_date(scheduled_for, tz)));
+    }
+    if matches!(
+        task.status,
+        TaskStatus::Now | TaskStatus::Waiting | TaskStatus::Someday
+    ) {
+        parts.push(format!("status:{}", task.status.as_str()));
+    }
+    if task.status == TaskStatus::Done {
+        if let Some(priority) = task.priority {
+            parts.push(format!("pri:{}", priority_letter(priority)));
+        }
+    }
+
+    parts.join(" ")
+}
+
+/// Parse one todo.txt line, or say why it can't be imported
+fn parse_todotxt_line(line: &str, tz: &Tz) -> Result<TodoTxtTask, String> {
+    let mut words = line.split_whitespace().peekable();
+    let mut task = TodoTxtTask {
+        original: line.trim().to_string(),
+        status: TaskStatus::Next,
+        ..Default::default()
+    };
+
+    if words.peek() == Some(&"x") {
+        words.next();
+        task.status = TaskStatus::Done;
+        if let Some(date) = words.peek().and_then(|word| parse_date(word)) {
+            words.next();
+            task.completed_at = Some(start_of_day(date, tz));
+        }
+    } else if let Some(priority) = words.peek().and_then(|word| parse_priority(word)) {
+        words.next();
+        task.priority = Some(priority);
+    }
+    if let Some(date) = words.peek().and_then(|word| parse_date(word)) {
+        words.next();
+        task.created_at = Some(start_of_day(date, tz));
+    }
+
+    let mut title: Vec<&str> = Vec::new();
+    for word in words {
+        if let Some(project) = word.strip_prefix('+').filter(|p| !p.is_empty()) {
+            task.project.get_or_insert_with(|| project.to_string());
+        } else if word.len() > 1 && word.starts_with('@') {
+            task.context = Some(normalize_context(word).map_err(|e| e.to_string())?);
+        } else if word.len() > 1 && word.starts_with('#') {
+            let tag = normalize_tag(word).map_err(|e| e.to_string())?;
+            if !task.tags.contains(&tag) {
+                task.tags.push(tag);
+            }
+        } else if let Some(value) = word.strip_prefix("due:") {
+            let date = parse_date(value).ok_or_else(|| format!("Invalid due date: {}", value))?;
+            let time = NaiveTime::from_hms_opt(DUE_HOUR, 0, 0).expect("valid time");
+            task.scheduled_for = Some(local_datetime(tz, date.and_time(time)).timestamp());
+        } else if let Some(value) = word.strip_prefix("status:") {
+            let status = TaskStatus::from_str(value)
+                .filter(|status| *status != TaskStatus::Done)
+                .ok_or_else(|| format!("Invalid status: {}", value))?;
+            if task.status != TaskStatus::Done {
+                task.status = status;
+            }
+        } else if let Some(value) = word.strip_prefix("pri:") {
+            let priority = parse_priority(&format!("({})", value))
+                .ok_or_else(|| format!("Invalid priority: {}", value))?;
+            task.priority = Some(priority);
+        } else {
+            title.push(word);
+        }
+    }
+
+    task.title = title.join(" ");
+    if task.title.is_empty() {
+        return Err("No task title".to_string());
+    }
+
+    Ok(task)
+}
+
+/// `(A)` is high, `(B)` medium and anything lower low
+fn parse_priority(word: &str) -> Option<Priority> {
+    let letter = word.strip_prefix('(')?.strip_suffix(')')?;
+    match letter {
+        "A" => Some(Priority::High),
+        "B" => Some(Priority::Medium),
+        _ if letter.len() == 1 && letter.chars().all(|c| c.is_ascii_uppercase()) => {
+            Some(Priority::Low)
+        }
+        _ => None,
+    }
+}
+
+fn priority_letter(priority: Priority) -> char {
+    match priority {
+        Priority::High => 'A',
+        Priority::Medium => 'B',
+        Priority::Low => 'C',
+    }
+}
+
+fn parse_date(word: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(word, "%Y-%m-%d").ok()
+}
+
+fn start_of_day(date: NaiveDate, tz: &Tz) -> i64 {
+    local_datetime(tz, date.and_time(NaiveTime::MIN)).timestamp()
+}
+
+/// A timestamp as a local `YYYY-MM-DD`
+pub(crate) fn format_date(timestamp: i64, tz: &Tz) -> String {
+    DateTime::from_timestamp(timestamp, 0)
+        .map(|dt| dt.with_timezone(tz).format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+/// A project title as a single todo.txt word
+fn project_key(title: &str) -> String {
+    title.split_whitespace().collect::<Vec<_>>().join("-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::{tempdir, TempDir};
+
+    fn setup_test_db() -> (TempDir, Database) {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let db = Database::new(db_path).unwrap();
+        db.set_timezone("UTC").unwrap();
+        (dir, db)
+    }
+
+    #[test]
+    fn test_parse_line() {
+        let tz = Tz::UTC;
+        let task = parse_todotxt_line(
+            "(A) 2024-01-01 Call mom +Family-Stuff @phone #urgent due:2024-01-05",
+            &tz,
+        )
+        .unwrap();
+        assert_eq!(task.title, "Call mom");
+        assert_eq!(task.priority, Some(Priority::High));
+        assert_eq!(task.project.as_deref(), Some("Family-Stuff"));
+        assert_eq!(task.context.as_deref(), Some("phone"));
+        assert_eq!(task.tags, vec!["urgent"]);
+        assert_eq!(task.created_at, Some(1_704_067_200));
+        assert_eq!(
+            task.scheduled_for,
+            Some(1_704_067_200 + 4 * 86_400 + 9 * 3_600)
+        );
+        assert_eq!(task.status, TaskStatus::Next);
+
+        let done = parse_todotxt_line("x 2024-01-03 2024-01-01 File taxes pri:C", &tz).unwrap();
+        assert_eq!(done.status, TaskStatus::Done);
+        assert_eq!(done.completed_at, Some(1_704_240_000));
+        assert_eq!(done.priority, Some(Priority::Low));
+        assert_eq!(done.title, "File taxes");
+
+        let waiting = parse_todotxt_line("Hear back from Bob status:waiting", &tz).unwrap();
+        assert_eq!(waiting.status, TaskStatus::Waiting);
+
+        // Not a priority or a completion marker, just words
+        let plain = parse_todotxt_line("(a) xylophone lessons", &tz).unwrap();
+        assert_eq!(plain.title, "(a) xylophone lessons");
+        assert_eq!(plain.priority, None);
+
+        assert!(parse_todotxt_line("(B) +Project @home", &tz).is_err());
+        assert!(parse_todotxt_line("Dentist due:soon", &tz).is_err());
+    }
+
+    #[test]
+    fn test_import_and_export() {
+        let (_dir, db) = setup_test_db();
+        db.create_project("Kitchen Remodel", None).unwrap();
+
+        let text = "(B) 2024-01-01 Order tiles +Kitchen-Remodel @errands\n\
+                    \n\
+                    x 2024-01-03 Pay deposit +Kitchen-Remodel\n\
+                    Plan trip +Holiday #travel\n\
+                    @home\n";
+
+        let report = db.import_todotxt(text, true).unwrap();
+        assert!(report.dry_run);
+        assert_eq!(report.tasks.len(), 3);
+        assert_eq!(report.tasks[2].line, 4);
+        assert_eq!(report.new_projects, vec!["Holiday"]);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].line, 5);
+        assert!(db.live_tasks().unwrap().is_empty());
+        assert_eq!(db.get_all_projects().unwrap().len(), 1);
+
+        db.import_todotxt(text, false).unwrap();
+        let tasks = db.live_tasks().unwrap();
+        assert_eq!(tasks.len(), 3);
+        assert_eq!(db.get_all_projects().unwrap().len(), 2);
+        let done = tasks.iter().find(|t| t.title == "Pay deposit").unwrap();
+        assert_eq!(done.completed_at, Some(1_704_240_000));
+        assert_eq!(done.source, "todotxt");
+
+        let exported = db.export_todotxt().unwrap();
+        let lines: Vec<&str> = exported.lines().collect();
+        assert_eq!(
+            lines[0],
+            "(B) 2024-01-01 Order tiles +Kitchen-Remodel @errands"
+        );
+        assert!(lines[2].starts_with("x 2024-01-03 "));
+        assert!(lines[2].ends_with(" Pay deposit +Kitchen-Remodel"));
+    }
+}
@@ -0,0 +1,229 @@
+use anyhow::{anyhow, bail, Result};
+use chrono::Utc;
+use rusqlite::{params, OptionalExtension};
+
+use super::connection::Database;
+use super::models::Task;
+use super::queries::TASK_COLUMNS;
+
+const SECONDS_PER_DAY: i64 = 86400;
+
+impl Database {
+    /// Days a task stays in the trash before it is purged
+    pub fn trash_retention_days(&self) -> Result<i64> {
+        Ok(self
+            .get_setting("trash_retention_days")?
+            .and_then(|s| s.parse().ok())
+            .filter(|days| *days > 0)
+            .unwrap_or(30))
+    }
+
+    pub fn set_trash_retention_days(&self, days: i64) -> Result<()> {
+        if days < 1 {
+            bail!("Trash retention must be at least 1 day");
+        }
+        self.set_setting("trash_retention_days", &days.to_string())
+    }
+
+    /// Tasks in the trash, most recently deleted first. Anything past the
+    /// retention period is purged before listing.
+    pub fn get_trash(&self) -> Result<Vec<Task>> {
+        self.purge_expired_trash()?;
+
+        let mut stmt = self.conn().prepare(&format!(
+            "SELECT {} FROM tasks WHERE deleted_at IS NOT NULL
+             ORDER BY deleted_at DESC, created_at DESC",
+            TASK_COLUMNS
+        ))?;
+
+        let tasks = stmt
+            .query_map([], |row| self.row_to_task(row))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(tasks)
+    }
+
+    /// Take a task out of the trash along with the subtasks deleted with it.
+    /// A subtask whose parent is still in the trash comes back on its own.
+    pub fn restore_task(&self, id: &str) -> Result<Task> {
+        let deleted_at: Option<i64> = self
+            .conn()
+            .query_row(
+                "SELECT deleted_at FROM tasks WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .ok_or_else(|| anyhow!("Task {} not found", id))?;
+        let Some(deleted_at) = deleted_at else {
+            bail!("Task {} is not in the trash", id);
+        };
+
+        self.conn().execute(
+            "WITH RECURSIVE subtree(id) AS (
+                SELECT ?1
+                UNION
+                SELECT tasks.id FROM tasks JOIN subtree ON tasks.parent_id = subtree.id
+             )
+             UPDATE tasks SET deleted_at = NULL, sync_version = sync_version + 1
+             WHERE id IN subtree AND deleted_at = ?2",
+            params![id, deleted_at],
+        )?;
+
+        self.conn().execute(
+            "UPDATE tasks SET parent_id = NULL
+             WHERE id = ?1
+                AND parent_id IN (SELECT id FROM tasks WHERE deleted_at IS NOT NULL)",
+            params![id],
+        )?;
+
+        // Keep the one-NOW rule: a restored NOW task yields to the current one
+        self.conn().execute(
+            "UPDATE tasks SET status = 'next'
+             WHERE id = ?1 AND status = 'now'
+                AND EXISTS (
+                    SELECT 1 FROM tasks
+                    WHERE status = 'now' AND deleted_at IS NULL AND id != ?1
+                )",
+            params![id],
+        )?;
+
+        self.get_task(id)
+    }
+
+    /// Permanently delete a task in the trash, and any subtasks under it
+    pub fn purge_task(&self, id: &str) -> Result<()> {
+        let purged = self.conn().execute(
+            "DELETE FROM tasks WHERE id = ?1 AND deleted_at IS NOT NULL",
+            params![id],
+        )?;
+        if purged == 0 {
+            bail!("Task {} is not in the trash", id);
+        }
+        Ok(())
+    }
+
+    /// Permanently delete everything in the trash. Returns how many tasks went.
+    pub fn empty_trash(&self) -> Result<usize> {
+        let purged = self
+            .conn()
+            .execute("DELETE FROM tasks WHERE deleted_at IS NOT NULL", [])?;
+        Ok(purged)
+    }
+
+    /// Permanently delete tasks that have been in the trash longer than the
+    /// retention period. Returns how many tasks went.
+    pub fn purge_expired_trash(&self) -> Result<usize> {
+        let cutoff = Utc::now().timestamp() - self.trash_retention_days()? * SECONDS_PER_DAY;
+        let purged = self.conn().execute(
+            "DELETE FROM tasks WHERE deleted_at IS NOT NULL AND deleted_at <= ?1",
+            params![cutoff],
+        )?;
+        Ok(purged)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{NewTask, TaskQuery, TaskStatus};
+    use tempfile::{tempdir, TempDir};
+
+    fn setup_test_db() -> (TempDir, Database) {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let db = Database::new(db_path).unwrap();
+        (dir, db)
+    }
+
+    fn insert(db: &Database, title: &str, status: TaskStatus, parent_id: Option<&str>) -> Task {
+        db.insert_task(&NewTask {
+            title: title.to_string(),
+            status,
+            source: "test".to_string(),
+            parent_id: parent_id.map(str::to_string),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_delete_and_restore() {
+        let (_dir, db) = setup_test_db();
+        let parent = insert(&db, "Plan party", TaskStatus::Next, None);
+        let child = insert(&db, "Send invites", TaskStatus::Next, Some(&parent.id));
+        insert(&db, "Keep me", TaskStatus::Next, None);
+
+        db.delete_task(&parent.id).unwrap();
+
+        // Trashed tasks disappear from every view but the trash
+        assert!(db.get_task(&parent.id).is_err());
+        assert!(db.get_task(&child.id).is_err());
+        assert_eq!(db.get_dashboard_data().unwrap().next_tasks.len(), 1);
+        assert_eq!(db.list_tasks(&TaskQuery::default()).unwrap().tasks.len(), 1);
+
+        let trash = db.get_trash().unwrap();
+        assert_eq!(trash.len(), 2);
+        assert!(trash.iter().all(|task| task.deleted_at.is_some()));
+
+        let restored = db.restore_task(&parent.id).unwrap();
+        assert_eq!(restored.deleted_at, None);
+        assert_eq!(restored.sync_version, parent.sync_version + 2);
+        assert_eq!(db.get_task(&child.id).unwrap().parent_id, Some(parent.id));
+        assert!(db.get_trash().unwrap().is_empty());
+
+        assert!(db.restore_task(&child.id).is_err());
+        assert!(db.restore_task("missing").is_err());
+    }
+
+    #[test]
+    fn test_restore_keeps_rules() {
+        let (_dir, db) = setup_test_db();
+        let parent = insert(&db, "Parent", TaskStatus::Next, None);
+        let child = insert(&db, "Child", TaskStatus::Now, Some(&parent.id));
+
+        db.delete_task(&parent.id).unwrap();
+        let now = insert(&db, "Doing this", TaskStatus::Now, None);
+
+        // The subtask comes back on its own, and doesn't steal NOW
+        let restored = db.restore_task(&child.id).unwrap();
+        assert_eq!(restored.parent_id, None);
+        assert_eq!(restored.status, TaskStatus::Next);
+        assert_eq!(db.get_task(&now.id).unwrap().status, TaskStatus::Now);
+        assert_eq!(db.get_trash().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_purge() {
+        let (_dir, db) = setup_test_db();
+        let a = insert(&db, "A", TaskStatus::Next, None);
+        let b = insert(&db, "B", TaskStatus::Next, None);
+        let c = insert(&db, "C", TaskStatus::Next, None);
+
+        assert!(db.purge_task(&a.id).is_err());
+        db.delete_task(&a.id).unwrap();
+        db.purge_task(&a.id).unwrap();
+        assert!(db.restore_task(&a.id).is_err());
+
+        db.delete_task(&b.id).unwrap();
+        db.delete_task(&c.id).unwrap();
+
+        // Backdate one deletion past the retention period
+        db.set_trash_retention_days(7).unwrap();
+        assert!(db.set_trash_retention_days(0).is_err());
+        db.conn()
+            .execute(
+                "UPDATE tasks SET deleted_at = deleted_at - 8 * 86400 WHERE id = ?1",
+                params![b.id],
+            )
+            .unwrap();
+        assert_eq!(db.purge_expired_trash().unwrap(), 1);
+
+        let trash = db.get_trash().unwrap();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].id, c.id);
+
+        assert_eq!(db.empty_trash().unwrap(), 1);
+        assert!(db.get_trash().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,255 @@
+use anyhow::{bail, Result};
+use chrono::Utc;
+use rusqlite::{params, Row};
+use uuid::Uuid;
+
+use super::connection::Database;
+use super::models::{FollowUp, Task, TaskStatus, WaitingTask};
+use super::queries::TASK_COLUMNS;
+
+const SECONDS_PER_DAY: i64 = 86400;
+
+pub(crate) const FOLLOW_UP_COLUMNS: &str = "id, task_id, nudged_at, note";
+
+impl Database {
+    /// Days between follow-ups on WAITING tasks
+    pub fn follow_up_interval_days(&self) -> Result<i64> {
+        Ok(self
+            .get_setting("follow_up_interval_days")?
+            .and_then(|s| s.parse().ok())
+            .filter(|days| *days > 0)
+            .unwrap_or(3))
+    }
+
+    /// `waiting_since` and `follow_up_at` after a status change. Entering
+    /// WAITING starts the clock and schedules a follow-up unless one is
+    /// already set; leaving it clears both.
+    pub(crate) fn waiting_fields(
+        &self,
+        previous: &TaskStatus,
+        status: &TaskStatus,
+        waiting_since: Option<i64>,
+        follow_up_at: Option<i64>,
+    ) -> Result<(Option<i64>, Option<i64>)> {
+        if *status != TaskStatus::Waiting {
+            return Ok((None, None));
+        }
+        if *previous == TaskStatus::Waiting {
+            return Ok((waiting_since, follow_up_at));
+        }
+
+        let now = Utc::now().timestamp();
+        let follow_up_at = match follow_up_at {
+            Some(ts) => ts,
+            None => now + self.follow_up_interval_days()? * SECONDS_PER_DAY,
+        };
+        Ok((Some(now), Some(follow_up_at)))
+    }
+
+    /// WAITING tasks, soonest follow-up first
+    pub fn get_waiting_tasks(&self, limit: i64, context: Option<&str>) -> Result<Vec<WaitingTask>> {
+        let now = Utc::now().timestamp();
+
+        let mut stmt = self.conn().prepare(&format!(
+            "SELECT {},
+                (SELECT MAX(nudged_at) FROM follow_ups WHERE follow_ups.task_id = tasks.id)
+                    AS last_follow_up_at
+             FROM tasks
+             WHERE status = 'waiting' AND deleted_at IS NULL
+                AND (?2 IS NULL OR context = ?2 COLLATE NOCASE OR context IS NULL)
+             ORDER BY follow_up_at IS NULL, follow_up_at ASC, created_at DESC
+             LIMIT ?1",
+            TASK_COLUMNS
+        ))?;
+
+        let tasks = stmt
+            .query_map(params![limit, context], |row| {
+                let task = self.row_to_task(row)?;
+                let last_follow_up_at = row.get("last_follow_up_at")?;
+                Ok(WaitingTask {
+                    follow_up_overdue: task.follow_up_at.is_some_and(|ts| ts <= now),
+                    days_waiting: task
+                        .waiting_since
+                        .map_or(0, |since| (now - since) / SECONDS_PER_DAY),
+                    last_follow_up_at,
+                    task,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(tasks)
+    }
+
+    /// Record that we chased a WAITING task and push its follow-up date out
+    /// by `next_in_days` (default: the follow-up interval setting)
+    pub fn log_follow_up(
+        &self,
+        task_id: &str,
+        note: Option<&str>,
+        next_in_days: Option<i64>,
+    ) -> Result<Task> {
+        let task = self.get_task(task_id)?;
+        if task.status != TaskStatus::Waiting {
+            bail!("Only WAITING tasks can be followed up");
+        }
+
+        let next_in_days = match next_in_days {
+            Some(days) if days <= 0 => bail!("Follow-up interval must be at least 1 day"),
+            Some(days) => days,
+            None => self.follow_up_interval_days()?,
+        };
+
+        let now = Utc::now().timestamp();
+        let note = note.map(str::trim).filter(|note| !note.is_empty());
+
+        self.conn().execute(
+            "INSERT INTO follow_ups (id, task_id, nudged_at, note) VALUES (?1, ?2, ?3, ?4)",
+            params![Uuid::new_v4().to_string(), task_id, now, note],
+        )?;
+        self.conn().execute(
+            "UPDATE tasks SET follow_up_at = ?1, sync_version = sync_version + 1 WHERE id = ?2",
+            params![now + next_in_days * SECONDS_PER_DAY, task_id],
+        )?;
+
+        self.get_task(task_id)
+    }
+
+    /// Follow-up nudges logged for a task, newest first
+    pub fn get_follow_ups(&self, task_id: &str) -> Result<Vec<FollowUp>> {
+        let mut stmt = self.conn().prepare(&format!(
+            "SELECT {} FROM follow_ups
+             WHERE task_id = ?1 ORDER BY nudged_at DESC, rowid DESC",
+            FOLLOW_UP_COLUMNS
+        ))?;
+
+        let follow_ups = stmt
+            .query_map(params![task_id], row_to_follow_up)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(follow_ups)
+    }
+}
+
+/// Helper: Convert database row to FollowUp
+pub(crate) fn row_to_follow_up(row: &Row) -> rusqlite::Result<FollowUp> {
+    Ok(FollowUp {
+        id: row.get(0)?,
+        task_id: row.get(1)?,
+        nudged_at: row.get(2)?,
+        note: row.get(3)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::{NewTask, TaskPatch};
+    use tempfile::{tempdir, TempDir};
+
+    fn setup_test_db() -> (TempDir, Database) {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let db = Database::new(db_path).unwrap();
+        (dir, db)
+    }
+
+    fn create(db: &Database, title: &str, status: TaskStatus) -> Task {
+        db.insert_task(&NewTask {
+            title: title.to_string(),
+            status,
+            source: "test".to_string(),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_waiting_clock() {
+        let (_dir, db) = setup_test_db();
+        let now = Utc::now().timestamp();
+
+        // Created as WAITING: clock starts and a follow-up is due in 3 days
+        let task = create(&db, "Contract", TaskStatus::Waiting);
+        assert!(task.waiting_since.unwrap() >= now);
+        assert_eq!(
+            task.follow_up_at.unwrap() - task.waiting_since.unwrap(),
+            3 * SECONDS_PER_DAY
+        );
+
+        // Leaving WAITING clears both
+        db.update_task_status(&task.id, TaskStatus::Next).unwrap();
+        let task = db.get_task(&task.id).unwrap();
+        assert_eq!((task.waiting_since, task.follow_up_at), (None, None));
+
+        // An explicit follow-up date is kept when entering WAITING
+        let patch: TaskPatch = serde_json::from_str(&format!(
+            r#"{{"status": "waiting", "waiting_on": " Alice ", "follow_up_at": {}}}"#,
+            now + 60
+        ))
+        .unwrap();
+        let task = db.update_task(&task.id, patch).unwrap();
+        assert_eq!(task.waiting_on, Some("Alice".to_string()));
+        assert_eq!(task.follow_up_at, Some(now + 60));
+
+        // Other edits leave the clock alone
+        let patch: TaskPatch = serde_json::from_str(r#"{"title": "Signed contract"}"#).unwrap();
+        let edited = db.update_task(&task.id, patch).unwrap();
+        assert_eq!(edited.waiting_since, task.waiting_since);
+        assert_eq!(edited.follow_up_at, task.follow_up_at);
+
+        // Only WAITING tasks take a follow-up date
+        let other = create(&db, "Other", TaskStatus::Next);
+        let patch: TaskPatch = serde_json::from_str(r#"{"follow_up_at": 1}"#).unwrap();
+        assert!(db.update_task(&other.id, patch).is_err());
+    }
+
+    #[test]
+    fn test_overdue_follow_ups_on_dashboard() {
+        let (_dir, db) = setup_test_db();
+        let now = Utc::now().timestamp();
+
+        let later = create(&db, "Later", TaskStatus::Waiting);
+        let overdue = create(&db, "Overdue", TaskStatus::Waiting);
+        let patch: TaskPatch =
+            serde_json::from_str(&format!(r#"{{"follow_up_at": {}}}"#, now - 60)).unwrap();
+        db.update_task(&overdue.id, patch).unwrap();
+
+        let dashboard = db.get_dashboard_data().unwrap();
+        let waiting: Vec<_> = dashboard
+            .waiting_tasks
+            .iter()
+            .map(|w| (w.task.id.as_str(), w.follow_up_overdue))
+            .collect();
+        assert_eq!(
+            waiting,
+            vec![(overdue.id.as_str(), true), (later.id.as_str(), false)]
+        );
+        assert_eq!(dashboard.waiting_tasks[0].days_waiting, 0);
+    }
+
+    #[test]
+    fn test_log_follow_up() {
+        let (_dir, db) = setup_test_db();
+        let now = Utc::now().timestamp();
+
+        let next = create(&db, "Not waiting", TaskStatus::Next);
+        assert!(db.log_follow_up(&next.id, None, None).is_err());
+
+        let task = create(&db, "Invoice", TaskStatus::Waiting);
+        assert!(db.log_follow_up(&task.id, None, Some(0)).is_err());
+
+        db.log_follow_up(&task.id, Some("Emailed"), None).unwrap();
+        let task = db.log_follow_up(&task.id, Some("  "), Some(7)).unwrap();
+        assert!(task.follow_up_at.unwrap() >= now + 7 * SECONDS_PER_DAY);
+        assert_eq!(task.sync_version, 3);
+
+        let follow_ups = db.get_follow_ups(&task.id).unwrap();
+        assert_eq!(follow_ups.len(), 2);
+        assert_eq!(follow_ups[0].note, None);
+        assert_eq!(follow_ups[1].note, Some("Emailed".to_string()));
+
+        let waiting = db.get_waiting_tasks(10, None).unwrap();
+        assert_eq!(waiting[0].last_follow_up_at, Some(follow_ups[0].nudged_at));
+        assert!(!waiting[0].follow_up_overdue);
+    }
+}
@@ -1,12 +1,18 @@
 mod commands;
 mod db;
 mod nlp;
+mod snapshots;
+mod sync;
 
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use tauri::{Emitter, Manager};
 
 pub struct AppState {
-    pub db: Mutex<db::Database>,
+    pub db: Arc<Mutex<db::Database>>,
+    pub sync: sync::SyncEngine,
+    /// Quick-capture parser; register custom extractors when building it
+    pub nlp: nlp::Pipeline,
+    pub snapshots: snapshots::SnapshotStore,
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -52,18 +58,89 @@ pub fn run() {
             let db_path = app_data_dir.join("taskflow.db");
             let database = db::Database::new(db_path).expect("Failed to initialize database");
 
+            // Purge tasks past the trash retention period; a failure here
+            // just leaves them for the next launch
+            let _ = database.purge_expired_trash();
+
+            let db = Arc::new(Mutex::new(database));
+
+            // Start background git sync (no-op until git_sync_enabled is set)
+            let sync_engine = sync::SyncEngine::new(app_data_dir.join("sync"));
+            sync::spawn_background_sync(Arc::clone(&db), sync_engine.clone());
+
+            // Daily snapshots of the database into the backups folder
+            let snapshot_store = snapshots::SnapshotStore::new(app_data_dir.join("backups"));
+            snapshots::spawn_snapshot_worker(Arc::clone(&db), snapshot_store.clone());
+
             // Set up app state
             app.manage(AppState {
-                db: Mutex::new(database),
+                db,
+                sync: sync_engine,
+                nlp: nlp::Pipeline::default(),
+                snapshots: snapshot_store,
             });
 
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             commands::quick_capture,
+            commands::preview_capture,
             commands::get_dashboard_data,
+            commands::list_tasks,
+            commands::search_tasks,
             commands::update_task_status,
+            commands::update_task,
             commands::delete_task,
+            commands::undo,
+            commands::redo,
+            commands::get_undo_state,
+            commands::get_task_history,
+            commands::get_stats,
+            commands::export_todotxt,
+            commands::export_markdown,
+            commands::import_todotxt,
+            commands::export_backup,
+            commands::restore_backup,
+            commands::create_snapshot,
+            commands::list_snapshots,
+            commands::restore_snapshot,
+            commands::configure_snapshots,
+            commands::get_trash,
+            commands::restore_task,
+            commands::purge_task,
+            commands::empty_trash,
+            commands::get_trash_retention_days,
+            commands::set_trash_retention_days,
+            commands::add_task_tag,
+            commands::remove_task_tag,
+            commands::list_tags,
+            commands::get_tasks_by_tag,
+            commands::start_review,
+            commands::get_review_tasks,
+            commands::get_review_projects,
+            commands::record_review_decision,
+            commands::finish_review,
+            commands::sync_now,
+            commands::get_sync_log,
+            commands::configure_sync,
+            commands::get_timezone,
+            commands::set_timezone,
+            commands::list_extractors,
+            commands::configure_extractors,
+            commands::log_follow_up,
+            commands::get_follow_ups,
+            commands::suggest_now_task,
+            commands::list_contexts,
+            commands::get_current_context,
+            commands::set_current_context,
+            commands::create_project,
+            commands::get_projects,
+            commands::get_project,
+            commands::update_project,
+            commands::delete_project,
+            commands::get_project_tasks,
+            commands::get_subtasks,
+            commands::get_task_progress,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
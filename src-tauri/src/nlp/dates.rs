@@ -0,0 +1,371 @@
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, NaiveTime, TimeDelta, Weekday};
+use chrono_tz::Tz;
+use regex::Regex;
+use std::ops::Range;
+
+use super::{Capture, Extractor, TokenKind};
+use crate::db::recurrence::local_datetime;
+
+/// Hour of day used when a phrase names a day but no time
+const DEFAULT_HOUR: u32 = 9;
+/// Hour implied by "this evening"
+const EVENING_HOUR: u32 = 18;
+/// Hour implied by "tonight"
+const TONIGHT_HOUR: u32 = 20;
+
+/// UTC timestamp of a local date and time
+pub(super) fn at_local(date: NaiveDate, time: NaiveTime, tz: &Tz) -> i64 {
+    local_datetime(tz, date.and_time(time)).timestamp()
+}
+
+/// How specific a date phrase is. When several occur, the most specific
+/// wins, and among equally specific ones the first in the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DatePrecedence {
+    /// "tonight", "this evening": today, with an implied time
+    Today,
+    /// "tomorrow", "in 2 days", "next friday", "end of week", "next month"
+    Relative,
+    /// "on March 5", "2026-11-02"
+    Absolute,
+}
+
+/// Date and time phrases found in quick-capture input
+#[derive(Debug, Default)]
+pub(super) struct Schedule {
+    /// Exact instant from "in 3 hours"; overrides every other phrase
+    instant: Option<i64>,
+    /// Chosen day, its precedence and position in the input
+    date: Option<(DatePrecedence, usize, NaiveDate)>,
+    /// Explicit time ("at 3pm", "14:30")
+    time: Option<NaiveTime>,
+    /// Time implied by the chosen day phrase ("tonight")
+    implied_time: Option<NaiveTime>,
+}
+
+impl Schedule {
+    pub(super) fn offer_date(
+        &mut self,
+        precedence: DatePrecedence,
+        position: usize,
+        date: NaiveDate,
+        implied_time: Option<NaiveTime>,
+    ) {
+        let better = match self.date {
+            None => true,
+            Some((current, current_pos, _)) => {
+                precedence > current || (precedence == current && position < current_pos)
+            }
+        };
+        if better {
+            self.date = Some((precedence, position, date));
+            self.implied_time = implied_time;
+        }
+    }
+
+    pub(super) fn offer_time(&mut self, time: NaiveTime) {
+        self.time.get_or_insert(time);
+    }
+
+    pub(super) fn has_day(&self) -> bool {
+        self.instant.is_some() || self.date.is_some()
+    }
+
+    pub(super) fn time_of_day(&self) -> NaiveTime {
+        self.time
+            .or(self.implied_time)
+            .unwrap_or_else(|| NaiveTime::from_hms_opt(DEFAULT_HOUR, 0, 0).expect("valid time"))
+    }
+
+    /// UTC timestamp for the parsed phrases. A time with no day means the
+    /// next time the clock reads that time; an implied time that has
+    /// already passed means now.
+    pub(super) fn resolve(&self, now: &DateTime<Tz>) -> Option<i64> {
+        if let Some(instant) = self.instant {
+            return Some(instant);
+        }
+
+        let tz = now.timezone();
+        match (self.date, self.time) {
+            (Some((_, _, date)), _) => {
+                let at = at_local(date, self.time_of_day(), &tz);
+                // "tonight" said after the implied hour means now, not earlier today
+                if self.time.is_none() && self.implied_time.is_some() {
+                    Some(at.max(now.timestamp()))
+                } else {
+                    Some(at)
+                }
+            }
+            (None, Some(time)) => {
+                let today = at_local(now.date_naive(), time, &tz);
+                if today > now.timestamp() {
+                    Some(today)
+                } else {
+                    Some(at_local(now.date_naive() + Duration::days(1), time, &tz))
+                }
+            }
+            (None, None) => None,
+        }
+    }
+}
+
+/// Dates and times: "tomorrow at 3pm", "on March 5", "in 3 hours", ...
+pub struct DateExtractor {
+    in_duration: Regex,
+    iso_date: Regex,
+    month_date: Regex,
+    tomorrow: Regex,
+    next_weekday: Regex,
+    end_of_week: Regex,
+    next_month: Regex,
+    evening: Regex,
+    meridiem: Regex,
+    clock: Regex,
+}
+
+impl DateExtractor {
+    pub fn new() -> Self {
+        const MONTH: &str = r"(jan(?:uary)?|feb(?:ruary)?|mar(?:ch)?|apr(?:il)?|may|june?|july?|aug(?:ust)?|sep(?:t(?:ember)?)?|oct(?:ober)?|nov(?:ember)?|dec(?:ember)?)";
+
+        DateExtractor {
+            in_duration: Regex::new(r"(?i)\bin (\d+) (minute|min|hour|hr|day|week)s?\b").unwrap(),
+            iso_date: Regex::new(r"\b(\d{4})-(\d{2})-(\d{2})\b").unwrap(),
+            month_date: Regex::new(&format!(
+                r"(?i)\b(?:on )?{}\.? (\d{{1,2}})(?:st|nd|rd|th)?\b(?:,? (\d{{4}})\b)?",
+                MONTH
+            ))
+            .unwrap(),
+            tomorrow: Regex::new(r"(?i)\btomorrow\b").unwrap(),
+            next_weekday: Regex::new(
+                r"(?i)\bnext (monday|tuesday|wednesday|thursday|friday|saturday|sunday)\b",
+            )
+            .unwrap(),
+            end_of_week: Regex::new(r"(?i)\b(?:by )?(?:the )?end of (?:the )?week\b").unwrap(),
+            next_month: Regex::new(r"(?i)\bnext month\b").unwrap(),
+            evening: Regex::new(r"(?i)\b(tonight|this evening)\b").unwrap(),
+            meridiem: Regex::new(r"(?i)\b(?:at )?(\d{1,2})(?::(\d{2}))? ?(am|pm)\b").unwrap(),
+            clock: Regex::new(r"(?i)\b(?:at )?([01]?\d|2[0-3]):([0-5]\d)\b").unwrap(),
+        }
+    }
+}
+
+impl Default for DateExtractor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Extractor for DateExtractor {
+    fn name(&self) -> &str {
+        "dates"
+    }
+
+    fn extract(&self, capture: &mut Capture) {
+        let text = capture.text().to_string();
+        let now = capture.now;
+        let today = now.date_naive();
+        // Consumed at the end, as patterns may overlap ("10:30 am" and "10:30")
+        let mut found: Vec<(TokenKind, Range<usize>, f32)> = Vec::new();
+
+        // "in 3 hours" is an exact instant; "in 3 days" is a day at the usual hour
+        for caps in self.in_duration.captures_iter(&text) {
+            let Ok(n) = caps[1].parse::<i64>() else {
+                continue;
+            };
+            let m = caps.get(0).unwrap();
+            // Amounts too large for a date are left in the title
+            let unit = caps[2].to_lowercase();
+            let delta = match unit.as_str() {
+                "minute" | "min" => TimeDelta::try_minutes(n),
+                "hour" | "hr" => TimeDelta::try_hours(n),
+                "day" => TimeDelta::try_days(n),
+                _ => TimeDelta::try_weeks(n),
+            };
+            let Some(delta) = delta else {
+                continue;
+            };
+            if matches!(unit.as_str(), "minute" | "min" | "hour" | "hr") {
+                let Some(instant) = now.checked_add_signed(delta) else {
+                    continue;
+                };
+                capture.schedule.instant.get_or_insert(instant.timestamp());
+            } else {
+                let Some(date) = today.checked_add_signed(delta) else {
+                    continue;
+                };
+                capture
+                    .schedule
+                    .offer_date(DatePrecedence::Relative, m.start(), date, None);
+            }
+            found.push((TokenKind::Date, m.range(), 0.95));
+        }
+
+        for caps in self.iso_date.captures_iter(&text) {
+            let date = NaiveDate::from_ymd_opt(
+                caps[1].parse().unwrap_or(0),
+                caps[2].parse().unwrap_or(0),
+                caps[3].parse().unwrap_or(0),
+            );
+            if let Some(date) = date {
+                let m = caps.get(0).unwrap();
+                capture
+                    .schedule
+                    .offer_date(DatePrecedence::Absolute, m.start(), date, None);
+                found.push((TokenKind::Date, m.range(), 1.0));
+            }
+        }
+
+        // A month and day without a year is the next time that date comes round
+        for caps in self.month_date.captures_iter(&text) {
+            let month = month_number(&caps[1]);
+            let day = caps[2].parse().unwrap_or(0);
+            let date = match caps.get(3) {
+                Some(year) => {
+                    NaiveDate::from_ymd_opt(year.as_str().parse().unwrap_or(0), month, day)
+                }
+                None => NaiveDate::from_ymd_opt(today.year(), month, day)
+                    .filter(|date| *date >= today)
+                    .or_else(|| NaiveDate::from_ymd_opt(today.year() + 1, month, day)),
+            };
+            if let Some(date) = date {
+                let m = caps.get(0).unwrap();
+                capture
+                    .schedule
+                    .offer_date(DatePrecedence::Absolute, m.start(), date, None);
+                // "may 5" could as well be prose; an "on" or a year makes it certain
+                let confidence =
+                    if m.as_str().to_lowercase().starts_with("on ") || caps.get(3).is_some() {
+                        0.95
+                    } else {
+                        0.8
+                    };
+                found.push((TokenKind::Date, m.range(), confidence));
+            }
+        }
+
+        for m in self.tomorrow.find_iter(&text) {
+            capture.schedule.offer_date(
+                DatePrecedence::Relative,
+                m.start(),
+                today + Duration::days(1),
+                None,
+            );
+            found.push((TokenKind::Date, m.range(), 1.0));
+        }
+
+        // "next Monday" is the next occurrence of that weekday
+        for caps in self.next_weekday.captures_iter(&text) {
+            let weekday = caps[1][..3].parse::<Weekday>().expect("weekday name");
+            let date = (1..=7)
+                .map(|d| today + Duration::days(d))
+                .find(|date| date.weekday() == weekday)
+                .expect("every weekday occurs within a week");
+            let m = caps.get(0).unwrap();
+            capture
+                .schedule
+                .offer_date(DatePrecedence::Relative, m.start(), date, None);
+            found.push((TokenKind::Date, m.range(), 0.95));
+        }
+
+        // End of week is this Friday, or next Friday once the weekend has started
+        for m in self.end_of_week.find_iter(&text) {
+            let date = (0..7)
+                .map(|d| today + Duration::days(d))
+                .find(|date| date.weekday() == Weekday::Fri)
+                .expect("every weekday occurs within a week");
+            capture
+                .schedule
+                .offer_date(DatePrecedence::Relative, m.start(), date, None);
+            found.push((TokenKind::Date, m.range(), 0.9));
+        }
+
+        // Next month is the first day of next month
+        for m in self.next_month.find_iter(&text) {
+            let date = NaiveDate::from_ymd_opt(today.year(), today.month(), 1)
+                .and_then(|first| first.checked_add_months(Months::new(1)))
+                .expect("valid date");
+            capture
+                .schedule
+                .offer_date(DatePrecedence::Relative, m.start(), date, None);
+            found.push((TokenKind::Date, m.range(), 0.9));
+        }
+
+        for caps in self.evening.captures_iter(&text) {
+            let hour = if caps[1].eq_ignore_ascii_case("tonight") {
+                TONIGHT_HOUR
+            } else {
+                EVENING_HOUR
+            };
+            let m = caps.get(0).unwrap();
+            capture.schedule.offer_date(
+                DatePrecedence::Today,
+                m.start(),
+                today,
+                NaiveTime::from_hms_opt(hour, 0, 0),
+            );
+            found.push((TokenKind::Date, m.range(), 0.9));
+        }
+
+        // Times: "3pm", "at 10:30 am", "14:30"; the first one wins
+        for caps in self.meridiem.captures_iter(&text) {
+            let hour: u32 = caps[1].parse().unwrap_or(0);
+            let minute = caps.get(2).map_or(Some(0), |m| m.as_str().parse().ok());
+            let hour = match (hour, caps[3].to_lowercase().as_str()) {
+                (12, "am") => Some(0),
+                (1..=11, "am") | (12, "pm") => Some(hour),
+                (1..=11, _) => Some(hour + 12),
+                _ => None,
+            };
+            if let Some(time) = hour
+                .zip(minute)
+                .and_then(|(h, m)| NaiveTime::from_hms_opt(h, m, 0))
+            {
+                let m = caps.get(0).unwrap();
+                capture.offer_time(time);
+                let confidence = if caps[0].to_lowercase().starts_with("at ") {
+                    0.95
+                } else {
+                    0.85
+                };
+                found.push((TokenKind::Time, m.range(), confidence));
+            }
+        }
+        for caps in self.clock.captures_iter(&text) {
+            let time = NaiveTime::from_hms_opt(
+                caps[1].parse().unwrap_or(0),
+                caps[2].parse().unwrap_or(0),
+                0,
+            );
+            let m = caps.get(0).unwrap();
+            if found.iter().any(|(_, range, _)| range.contains(&m.start())) {
+                continue;
+            }
+            if let Some(time) = time {
+                capture.offer_time(time);
+            }
+            // A bare "14:30" might be a score or a verse rather than a time
+            let confidence = if caps[0].to_lowercase().starts_with("at ") {
+                0.9
+            } else {
+                0.7
+            };
+            found.push((TokenKind::Time, m.range(), confidence));
+        }
+
+        for (kind, range, confidence) in found {
+            capture.consume(kind, range, confidence);
+        }
+    }
+}
+
+/// Month number from an English month name or abbreviation
+fn month_number(name: &str) -> u32 {
+    const MONTHS: [&str; 12] = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ];
+    let prefix = name[..3].to_lowercase();
+    MONTHS
+        .iter()
+        .position(|m| *m == prefix)
+        .map_or(0, |i| i as u32 + 1)
+}
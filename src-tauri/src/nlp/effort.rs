@@ -0,0 +1,53 @@
+use regex::Regex;
+
+use super::{Capture, Extractor, TokenKind};
+use crate::db::Energy;
+
+/// Effort estimates ("~30m", "~2h", "~1h30m") and energy tags ("#low-energy")
+pub struct EffortExtractor {
+    duration: Regex,
+    energy: Regex,
+}
+
+impl EffortExtractor {
+    pub fn new() -> Self {
+        EffortExtractor {
+            duration: Regex::new(r"(?i)(?:^|\s)(~(?:(\d+)h)?(?:(\d+)(?:m|min|mins)?)?)\b").unwrap(),
+            energy: Regex::new(r"(?i)(?:^|\s)(#(low|medium|high)[-_]energy)\b").unwrap(),
+        }
+    }
+}
+
+impl Default for EffortExtractor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Extractor for EffortExtractor {
+    fn name(&self) -> &str {
+        "effort"
+    }
+
+    /// Only the first estimate and energy tag count
+    fn extract(&self, capture: &mut Capture) {
+        let text = capture.text().to_string();
+
+        // "~someday" matches with no numbers; it belongs to the status extractor
+        let estimate = self.duration.captures_iter(&text).find_map(|caps| {
+            let hours: i32 = caps.get(2).map_or(Ok(0), |h| h.as_str().parse()).ok()?;
+            let minutes: i32 = caps.get(3).map_or(Ok(0), |m| m.as_str().parse()).ok()?;
+            let total = hours.checked_mul(60)?.checked_add(minutes)?;
+            (total > 0).then(|| (caps.get(1).unwrap().range(), total))
+        });
+        if let Some((range, minutes)) = estimate {
+            capture.effort_minutes = Some(minutes);
+            capture.consume(TokenKind::Effort, range, 1.0);
+        }
+
+        if let Some(caps) = self.energy.captures(&text) {
+            capture.energy = Energy::parse(&caps[2].to_lowercase());
+            capture.consume(TokenKind::Energy, caps.get(1).unwrap().range(), 1.0);
+        }
+    }
+}
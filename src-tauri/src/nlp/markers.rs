@@ -0,0 +1,78 @@
+use regex::Regex;
+
+use super::{Capture, Extractor, TokenKind};
+
+/// The first `@context`
+pub struct ContextExtractor {
+    context: Regex,
+}
+
+impl ContextExtractor {
+    pub fn new() -> Self {
+        ContextExtractor {
+            context: Regex::new(r"@(\w+)").unwrap(),
+        }
+    }
+}
+
+impl Default for ContextExtractor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Extractor for ContextExtractor {
+    fn name(&self) -> &str {
+        "context"
+    }
+
+    fn extract(&self, capture: &mut Capture) {
+        let Some(caps) = self.context.captures(capture.text()) else {
+            return;
+        };
+        let context = caps[1].to_string();
+        let range = caps.get(0).unwrap().range();
+
+        capture.context = Some(context);
+        capture.consume(TokenKind::Context, range, 1.0);
+    }
+}
+
+/// Every `#tag`, lowercased and de-duplicated
+pub struct TagExtractor {
+    tag: Regex,
+}
+
+impl TagExtractor {
+    pub fn new() -> Self {
+        TagExtractor {
+            tag: Regex::new(r"(?:^|\s)#([\w-]+)").unwrap(),
+        }
+    }
+}
+
+impl Default for TagExtractor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Extractor for TagExtractor {
+    fn name(&self) -> &str {
+        "tags"
+    }
+
+    fn extract(&self, capture: &mut Capture) {
+        let text = capture.text().to_string();
+        for caps in self.tag.captures_iter(&text) {
+            let tag = caps[1].to_lowercase();
+            if !capture.tags.contains(&tag) {
+                capture.tags.push(tag);
+            }
+            // The match may start with the whitespace before the '#'
+            let m = caps.get(0).unwrap();
+            let start = m.start() + m.as_str().find('#').unwrap_or(0);
+            capture.consume(TokenKind::Tag, start..m.end(), 1.0);
+        }
+    }
+}
@@ -0,0 +1,855 @@
+mod dates;
+mod effort;
+mod markers;
+mod priority;
+mod recurrence;
+mod status;
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime};
+use chrono_tz::Tz;
+use serde::Serialize;
+use std::ops::Range;
+
+use crate::db::{Energy, Priority, Recurrence, TaskStatus};
+
+pub use dates::{DateExtractor, DatePrecedence};
+pub use effort::EffortExtractor;
+pub use markers::{ContextExtractor, TagExtractor};
+pub use priority::PriorityExtractor;
+pub use recurrence::RecurrenceExtractor;
+pub use status::StatusExtractor;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ParsedTask {
+    pub title: String,
+    pub scheduled_for: Option<i64>,
+    pub context: Option<String>,
+    pub tags: Vec<String>,
+    pub recurrence: Option<Recurrence>,
+    /// Initial status, if the input asked for one
+    pub status: Option<TaskStatus>,
+    pub waiting_on: Option<String>,
+    pub priority: Option<Priority>,
+    pub effort_minutes: Option<i32>,
+    pub energy: Option<Energy>,
+    /// Recognized phrases, in input order
+    pub tokens: Vec<ParsedToken>,
+}
+
+/// What a recognized phrase contributed to the task
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenKind {
+    Context,
+    Tag,
+    Date,
+    Time,
+    Recurrence,
+    Status,
+    Priority,
+    Effort,
+    Energy,
+}
+
+/// A phrase recognized in quick-capture input
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ParsedToken {
+    pub kind: TokenKind,
+    /// Byte offsets into the original input
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+    /// How sure the parser is that the phrase means what it took it to mean (0-1)
+    pub confidence: f32,
+}
+
+/// An extractor and whether the current settings run it
+#[derive(Debug, Clone, Serialize)]
+pub struct ExtractorInfo {
+    pub name: String,
+    pub enabled: bool,
+}
+
+/// One step of the capture pipeline. An extractor looks for its phrases in
+/// the text earlier extractors left behind, consumes them and fills in the
+/// matching parts of the capture.
+pub trait Extractor: Send + Sync {
+    /// Name used to enable and order extractors in settings
+    fn name(&self) -> &str;
+
+    fn extract(&self, capture: &mut Capture);
+}
+
+/// Quick-capture input being parsed
+pub struct Capture<'a> {
+    input: &'a str,
+    /// The input with consumed phrases blanked, so offsets stay valid
+    text: String,
+    tokens: Vec<ParsedToken>,
+    schedule: dates::Schedule,
+    pub now: DateTime<Tz>,
+    pub context: Option<String>,
+    pub tags: Vec<String>,
+    pub recurrence: Option<Recurrence>,
+    pub status: Option<TaskStatus>,
+    pub waiting_on: Option<String>,
+    pub priority: Option<Priority>,
+    pub effort_minutes: Option<i32>,
+    pub energy: Option<Energy>,
+}
+
+impl<'a> Capture<'a> {
+    fn new(input: &'a str, now: DateTime<Tz>) -> Self {
+        Capture {
+            input,
+            text: input.to_string(),
+            tokens: Vec::new(),
+            schedule: dates::Schedule::default(),
+            now,
+            context: None,
+            tags: Vec::new(),
+            recurrence: None,
+            status: None,
+            waiting_on: None,
+            priority: None,
+            effort_minutes: None,
+            energy: None,
+        }
+    }
+
+    /// Text not yet consumed by an earlier extractor
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Record a recognized phrase and remove it from the title
+    pub fn consume(&mut self, kind: TokenKind, range: Range<usize>, confidence: f32) {
+        self.tokens.push(ParsedToken {
+            kind,
+            start: range.start,
+            end: range.end,
+            text: self.input[range.clone()].to_string(),
+            confidence,
+        });
+        let spaces = " ".repeat(range.len());
+        self.text.replace_range(range, &spaces);
+    }
+
+    /// Propose a day for the task; the most specific phrase wins
+    pub fn offer_date(&mut self, precedence: DatePrecedence, position: usize, date: NaiveDate) {
+        self.schedule.offer_date(precedence, position, date, None);
+    }
+
+    /// Set the time of day, unless an earlier phrase already did
+    pub fn offer_time(&mut self, time: NaiveTime) {
+        self.schedule.offer_time(time);
+    }
+
+    fn finish(mut self) -> ParsedTask {
+        let tz = self.now.timezone();
+        let today = self.now.date_naive();
+
+        // A weekday or day-of-month rule with no explicit day starts at its first occurrence
+        let first_day = match &self.recurrence {
+            _ if self.schedule.has_day() => None,
+            Some(Recurrence::Weekly { weekdays, .. }) if !weekdays.is_empty() => (1..=7)
+                .map(|d| today + Duration::days(d))
+                .find(|day| weekdays.contains(&day.weekday())),
+            Some(Recurrence::Monthly { day, .. }) => (1..=62)
+                .map(|d| today + Duration::days(d))
+                .find(|date| date.day() == *day),
+            _ => None,
+        };
+
+        let scheduled_for = match first_day {
+            Some(day) => Some(dates::at_local(day, self.schedule.time_of_day(), &tz)),
+            None => self.schedule.resolve(&self.now),
+        };
+
+        self.tokens.sort_by_key(|token| token.start);
+
+        ParsedTask {
+            title: self.text.split_whitespace().collect::<Vec<_>>().join(" "),
+            scheduled_for,
+            context: self.context,
+            tags: self.tags,
+            recurrence: self.recurrence,
+            status: self.status,
+            waiting_on: self.waiting_on,
+            priority: self.priority,
+            effort_minutes: self.effort_minutes,
+            energy: self.energy,
+            tokens: self.tokens,
+        }
+    }
+}
+
+/// Ordered set of extractors. Regexes are compiled once, when an extractor
+/// is constructed, so a pipeline should be built once and reused.
+pub struct Pipeline {
+    extractors: Vec<Box<dyn Extractor>>,
+}
+
+impl Pipeline {
+    /// A pipeline with no extractors
+    pub fn empty() -> Self {
+        Pipeline {
+            extractors: Vec::new(),
+        }
+    }
+
+    /// Add an extractor at the end, replacing any with the same name
+    pub fn register(&mut self, extractor: impl Extractor + 'static) {
+        self.extractors.retain(|e| e.name() != extractor.name());
+        self.extractors.push(Box::new(extractor));
+    }
+
+    /// Registered extractor names, in default order
+    pub fn names(&self) -> Vec<&str> {
+        self.extractors.iter().map(|e| e.name()).collect()
+    }
+
+    /// Extractors in the order they would run, followed by disabled ones
+    pub fn describe(&self, enabled: Option<&[String]>) -> Vec<ExtractorInfo> {
+        let Some(enabled) = enabled else {
+            return self
+                .names()
+                .into_iter()
+                .map(|name| ExtractorInfo {
+                    name: name.to_string(),
+                    enabled: true,
+                })
+                .collect();
+        };
+
+        let known = self.names();
+        let active = enabled
+            .iter()
+            .filter(|name| known.contains(&name.as_str()))
+            .map(|name| ExtractorInfo {
+                name: name.clone(),
+                enabled: true,
+            });
+        let inactive = known
+            .iter()
+            .filter(|name| !enabled.iter().any(|e| e == *name))
+            .map(|name| ExtractorInfo {
+                name: name.to_string(),
+                enabled: false,
+            });
+        active.chain(inactive).collect()
+    }
+
+    /// Parse with every registered extractor, in registration order.
+    /// Relative dates are resolved against `now` in its timezone, and
+    /// `scheduled_for` is the matching UTC timestamp.
+    pub fn parse(&self, input: &str, now: DateTime<Tz>) -> ParsedTask {
+        let mut capture = Capture::new(input, now);
+        for extractor in &self.extractors {
+            extractor.extract(&mut capture);
+        }
+        capture.finish()
+    }
+
+    /// Parse with only the named extractors, in the given order. Unknown
+    /// names are ignored.
+    pub fn parse_with(&self, input: &str, now: DateTime<Tz>, enabled: &[String]) -> ParsedTask {
+        let mut capture = Capture::new(input, now);
+        for name in enabled {
+            if let Some(extractor) = self.extractors.iter().find(|e| e.name() == name) {
+                extractor.extract(&mut capture);
+            }
+        }
+        capture.finish()
+    }
+}
+
+impl Default for Pipeline {
+    /// The built-in extractors
+    fn default() -> Self {
+        let mut pipeline = Pipeline::empty();
+        pipeline.register(ContextExtractor::new());
+        // Before tags, so "#low-energy" is an energy level rather than a tag
+        pipeline.register(EffortExtractor::new());
+        pipeline.register(TagExtractor::new());
+        pipeline.register(StatusExtractor::new());
+        pipeline.register(PriorityExtractor::new());
+        pipeline.register(DateExtractor::new());
+        pipeline.register(RecurrenceExtractor::new());
+        pipeline
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Timelike, Weekday};
+
+    /// Wednesday 2026-03-04, 22:30 in New York (already Thursday in UTC)
+    fn reference_now() -> DateTime<Tz> {
+        chrono_tz::America::New_York
+            .with_ymd_and_hms(2026, 3, 4, 22, 30, 0)
+            .unwrap()
+    }
+
+    fn parse(input: &str) -> ParsedTask {
+        Pipeline::default().parse(input, reference_now())
+    }
+
+    fn local(ts: i64) -> DateTime<Tz> {
+        DateTime::from_timestamp(ts, 0)
+            .unwrap()
+            .with_timezone(&chrono_tz::America::New_York)
+    }
+
+    #[test]
+    fn test_context_extraction() {
+        let p = parse("Buy milk @groceries");
+        assert_eq!(p.title, "Buy milk");
+        assert_eq!(p.context, Some("groceries".to_string()));
+    }
+
+    #[test]
+    fn test_tag_extraction() {
+        let p = parse("#Work Write report #follow-up @office #work");
+        assert_eq!(p.title, "Write report");
+        assert_eq!(p.tags, vec!["work", "follow-up"]);
+        assert_eq!(p.context, Some("office".to_string()));
+    }
+
+    #[test]
+    fn test_tomorrow_parsing() {
+        let p = parse("Call mom tomorrow");
+        assert_eq!(p.title, "Call mom");
+
+        // Tomorrow in New York, not tomorrow in UTC, at 9 AM local
+        let scheduled = local(p.scheduled_for.unwrap());
+        assert_eq!(
+            scheduled,
+            chrono_tz::America::New_York
+                .with_ymd_and_hms(2026, 3, 5, 9, 0, 0)
+                .unwrap()
+        );
+        assert_eq!(p.scheduled_for, Some(1772719200));
+    }
+
+    #[test]
+    fn test_relative_days_across_dst() {
+        // US clocks go forward on 2026-03-08; 9 AM local is 13:00 UTC after that
+        let p = parse("Renew permit in 5 days");
+        assert_eq!(p.title, "Renew permit");
+        assert_eq!(
+            local(p.scheduled_for.unwrap()),
+            chrono_tz::America::New_York
+                .with_ymd_and_hms(2026, 3, 9, 9, 0, 0)
+                .unwrap()
+        );
+
+        let p = parse("Dentist next monday");
+        assert_eq!(p.title, "Dentist");
+        assert_eq!(
+            local(p.scheduled_for.unwrap()).date_naive().to_string(),
+            "2026-03-09"
+        );
+    }
+
+    #[test]
+    fn test_recurrence_weekdays() {
+        let p = parse("Team standup every Monday and Thursday");
+        assert_eq!(p.title, "Team standup");
+        assert_eq!(
+            p.recurrence,
+            Some(Recurrence::Weekly {
+                interval: 1,
+                weekdays: vec![Weekday::Mon, Weekday::Thu],
+            })
+        );
+
+        // First occurrence is the next Thursday, in local time
+        let scheduled = local(p.scheduled_for.unwrap());
+        assert_eq!(
+            scheduled,
+            chrono_tz::America::New_York
+                .with_ymd_and_hms(2026, 3, 5, 9, 0, 0)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_invalid_recurrence_left_in_title() {
+        let p = parse("Water plants every 0 days");
+        assert_eq!(p.title, "Water plants every 0 days");
+        assert!(p.recurrence.is_none());
+
+        let p = parse("Pay rent every month on the 45th");
+        assert_eq!(p.title, "Pay rent every month on the 45th");
+        assert!(p.recurrence.is_none());
+    }
+
+    #[test]
+    fn test_recurrence_intervals() {
+        let p = parse("Water plants every 2 weeks");
+        assert_eq!(p.title, "Water plants");
+        assert_eq!(
+            p.recurrence,
+            Some(Recurrence::Weekly {
+                interval: 2,
+                weekdays: vec![],
+            })
+        );
+        assert!(p.scheduled_for.is_none());
+
+        let p = parse("Stretch daily");
+        assert_eq!(p.title, "Stretch");
+        assert_eq!(p.recurrence, Some(Recurrence::Daily { interval: 1 }));
+
+        let p = parse("Pay rent every month on the 1st");
+        assert_eq!(p.title, "Pay rent");
+        assert_eq!(
+            p.recurrence,
+            Some(Recurrence::Monthly {
+                interval: 1,
+                day: 1
+            })
+        );
+
+        let p = parse("Replace filter every 30 days after completion");
+        assert_eq!(p.title, "Replace filter");
+        assert_eq!(p.recurrence, Some(Recurrence::AfterCompletion { days: 30 }));
+
+        let p = parse("Review budget every other week");
+        assert_eq!(
+            p.recurrence,
+            Some(Recurrence::Weekly {
+                interval: 2,
+                weekdays: vec![],
+            })
+        );
+
+        let p = parse("Back up photos every month");
+        assert_eq!(p.title, "Back up photos");
+        assert!(matches!(
+            p.recurrence,
+            Some(Recurrence::Monthly { interval: 1, .. })
+        ));
+
+        let p = parse("Call the bank");
+        assert!(p.recurrence.is_none());
+    }
+
+    fn new_york(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Tz> {
+        chrono_tz::America::New_York
+            .with_ymd_and_hms(y, m, d, h, min, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_explicit_times() {
+        let p = parse("Call Sam tomorrow at 3pm");
+        assert_eq!(p.title, "Call Sam");
+        assert_eq!(local(p.scheduled_for.unwrap()), new_york(2026, 3, 5, 15, 0));
+
+        let p = parse("Standup 10:30 am next friday");
+        assert_eq!(p.title, "Standup");
+        assert_eq!(
+            local(p.scheduled_for.unwrap()),
+            new_york(2026, 3, 6, 10, 30)
+        );
+
+        let p = parse("Deploy at 12am tomorrow");
+        assert_eq!(local(p.scheduled_for.unwrap()), new_york(2026, 3, 5, 0, 0));
+
+        // 14:30 has already passed today, so it means tomorrow
+        let p = parse("Sync with design 14:30");
+        assert_eq!(p.title, "Sync with design");
+        assert_eq!(
+            local(p.scheduled_for.unwrap()),
+            new_york(2026, 3, 5, 14, 30)
+        );
+
+        // 23:15 is still ahead today
+        let p = parse("Take out bins at 23:15");
+        assert_eq!(
+            local(p.scheduled_for.unwrap()),
+            new_york(2026, 3, 4, 23, 15)
+        );
+
+        let p = parse("Read 13pm chapter");
+        assert_eq!(p.title, "Read 13pm chapter");
+        assert!(p.scheduled_for.is_none());
+    }
+
+    #[test]
+    fn test_evening_phrases() {
+        let afternoon = new_york(2026, 3, 4, 15, 0);
+        let p = Pipeline::default().parse("Pack bags tonight", afternoon);
+        assert_eq!(p.title, "Pack bags");
+        assert_eq!(local(p.scheduled_for.unwrap()), new_york(2026, 3, 4, 20, 0));
+
+        let p = Pipeline::default().parse("Call dad this evening", afternoon);
+        assert_eq!(p.title, "Call dad");
+        assert_eq!(local(p.scheduled_for.unwrap()), new_york(2026, 3, 4, 18, 0));
+
+        // At 22:30 both hours have passed, so they mean now
+        let p = parse("Pack bags tonight");
+        assert_eq!(local(p.scheduled_for.unwrap()), reference_now());
+        let p = parse("Call dad this evening");
+        assert_eq!(local(p.scheduled_for.unwrap()), reference_now());
+
+        // An explicit time beats the implied one
+        let p = parse("Movie tonight at 9:45pm");
+        assert_eq!(p.title, "Movie");
+        assert_eq!(
+            local(p.scheduled_for.unwrap()),
+            new_york(2026, 3, 4, 21, 45)
+        );
+    }
+
+    #[test]
+    fn test_absolute_dates() {
+        let p = parse("Renew passport on March 5");
+        assert_eq!(p.title, "Renew passport");
+        assert_eq!(local(p.scheduled_for.unwrap()), new_york(2026, 3, 5, 9, 0));
+
+        // Dates already passed this year roll over to next year
+        let p = parse("File taxes Feb 2nd");
+        assert_eq!(p.title, "File taxes");
+        assert_eq!(local(p.scheduled_for.unwrap()), new_york(2027, 2, 2, 9, 0));
+
+        let p = parse("Conference December 3, 2027 at 8am");
+        assert_eq!(p.title, "Conference");
+        assert_eq!(local(p.scheduled_for.unwrap()), new_york(2027, 12, 3, 8, 0));
+
+        let p = parse("Submit report 2026-11-02");
+        assert_eq!(p.title, "Submit report");
+        assert_eq!(local(p.scheduled_for.unwrap()), new_york(2026, 11, 2, 9, 0));
+
+        let p = parse("Invalid 2026-02-30 date");
+        assert_eq!(p.title, "Invalid 2026-02-30 date");
+        assert!(p.scheduled_for.is_none());
+    }
+
+    #[test]
+    fn test_relative_periods() {
+        let p = parse("Finish draft by end of week");
+        assert_eq!(p.title, "Finish draft");
+        assert_eq!(local(p.scheduled_for.unwrap()), new_york(2026, 3, 6, 9, 0));
+
+        let p = parse("Plan trip next month");
+        assert_eq!(p.title, "Plan trip");
+        assert_eq!(local(p.scheduled_for.unwrap()), new_york(2026, 4, 1, 9, 0));
+
+        let p = parse("Check oven in 3 hours");
+        assert_eq!(p.title, "Check oven");
+        assert_eq!(
+            p.scheduled_for,
+            Some((reference_now() + Duration::hours(3)).timestamp())
+        );
+
+        let p = parse("Stretch in 45 minutes");
+        assert_eq!(
+            p.scheduled_for,
+            Some((reference_now() + Duration::minutes(45)).timestamp())
+        );
+
+        let p = parse("Follow up in 2 weeks");
+        assert_eq!(local(p.scheduled_for.unwrap()), new_york(2026, 3, 18, 9, 0));
+    }
+
+    #[test]
+    fn test_out_of_range_durations() {
+        // Amounts past what a date can hold stay in the title
+        let p = parse("call mom in 999999999 days");
+        assert_eq!(p.title, "call mom in 999999999 days");
+        assert_eq!(p.scheduled_for, None);
+
+        let p = parse("call mom in 99999999999 hours");
+        assert_eq!(p.title, "call mom in 99999999999 hours");
+        assert_eq!(p.scheduled_for, None);
+
+        let p = parse("call mom in 9999999999999999 weeks");
+        assert_eq!(p.scheduled_for, None);
+    }
+
+    #[test]
+    fn test_date_precedence() {
+        // Absolute dates beat relative ones, wherever they appear
+        let p = parse("Tomorrow prep slides for March 10");
+        assert_eq!(p.title, "prep slides for");
+        assert_eq!(local(p.scheduled_for.unwrap()), new_york(2026, 3, 10, 9, 0));
+
+        // Relative days beat "tonight", which still can't set the time
+        let p = parse("Email tonight or tomorrow");
+        assert_eq!(p.title, "Email or");
+        assert_eq!(local(p.scheduled_for.unwrap()), new_york(2026, 3, 5, 9, 0));
+
+        // Among equally specific phrases the first one wins
+        let p = parse("Call next monday, not tomorrow");
+        assert_eq!(local(p.scheduled_for.unwrap()), new_york(2026, 3, 9, 9, 0));
+
+        // An exact instant beats everything
+        let p = parse("Ping in 2 hours tomorrow at 5pm");
+        assert_eq!(
+            p.scheduled_for,
+            Some((reference_now() + Duration::hours(2)).timestamp())
+        );
+    }
+
+    #[test]
+    fn test_recurrence_with_time() {
+        let p = parse("Gym every Monday at 7am");
+        assert_eq!(p.title, "Gym");
+        assert_eq!(local(p.scheduled_for.unwrap()), new_york(2026, 3, 9, 7, 0));
+    }
+
+    #[test]
+    fn test_token_spans() {
+        let input = "Call Zoë @phone tomorrow at 3pm #Work every week";
+        let p = parse(input);
+        assert_eq!(p.title, "Call Zoë");
+
+        let kinds: Vec<_> = p.tokens.iter().map(|t| t.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TokenKind::Context,
+                TokenKind::Date,
+                TokenKind::Time,
+                TokenKind::Tag,
+                TokenKind::Recurrence,
+            ]
+        );
+
+        // Spans index the original input, even after a multi-byte character
+        for token in &p.tokens {
+            assert_eq!(&input[token.start..token.end], token.text);
+        }
+        let texts: Vec<_> = p.tokens.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec!["@phone", "tomorrow", "at 3pm", "#Work", "every week"]
+        );
+    }
+
+    #[test]
+    fn test_token_confidence() {
+        let p = parse("Meet at 10:30 on May 5");
+        assert!(p.tokens.iter().all(|t| t.confidence >= 0.9));
+
+        // Bare times and month names are recognized but less certain
+        let p = parse("Psalm 23:15 may 5");
+        assert_eq!(p.tokens.len(), 2);
+        assert!(p.tokens.iter().all(|t| t.confidence < 0.9));
+
+        let p = parse("Plain task");
+        assert!(p.tokens.is_empty());
+    }
+
+    /// "asap": today, within the hour
+    struct AsapExtractor;
+
+    impl Extractor for AsapExtractor {
+        fn name(&self) -> &str {
+            "asap"
+        }
+
+        fn extract(&self, capture: &mut Capture) {
+            if let Some(start) = capture.text().find("asap") {
+                let now = capture.now;
+                capture.offer_date(DatePrecedence::Relative, start, now.date_naive());
+                capture.offer_time(NaiveTime::from_hms_opt(now.hour() + 1, 0, 0).unwrap());
+                capture.consume(TokenKind::Date, start..start + 4, 0.9);
+            }
+        }
+    }
+
+    /// A leading "#word" is a context rather than a tag
+    struct HashContextExtractor;
+
+    impl Extractor for HashContextExtractor {
+        fn name(&self) -> &str {
+            "hash-context"
+        }
+
+        fn extract(&self, capture: &mut Capture) {
+            let text = capture.text();
+            if let Some(word) = text.strip_prefix('#') {
+                let len = word.find(' ').unwrap_or(word.len());
+                capture.context = Some(word[..len].to_string());
+                capture.consume(TokenKind::Context, 0..len + 1, 1.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_pipeline_selection() {
+        let pipeline = Pipeline::default();
+        assert_eq!(
+            pipeline.names(),
+            vec![
+                "context",
+                "effort",
+                "tags",
+                "status",
+                "priority",
+                "dates",
+                "recurrence"
+            ]
+        );
+
+        let only_context = vec!["context".to_string()];
+        let p = pipeline.parse_with("Call @phone tomorrow", reference_now(), &only_context);
+        assert_eq!(p.title, "Call tomorrow");
+        assert_eq!(p.context, Some("phone".to_string()));
+        assert!(p.scheduled_for.is_none());
+
+        let p = pipeline.parse_with("Call @phone tomorrow", reference_now(), &[]);
+        assert_eq!(p.title, "Call @phone tomorrow");
+        assert!(p.tokens.is_empty());
+
+        let info = pipeline.describe(Some(&["dates".to_string(), "nope".to_string()]));
+        let described: Vec<_> = info.iter().map(|i| (i.name.as_str(), i.enabled)).collect();
+        assert_eq!(
+            described,
+            vec![
+                ("dates", true),
+                ("context", false),
+                ("effort", false),
+                ("tags", false),
+                ("status", false),
+                ("priority", false),
+                ("recurrence", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_custom_extractors() {
+        let mut pipeline = Pipeline::default();
+        pipeline.register(AsapExtractor);
+        pipeline.register(HashContextExtractor);
+
+        let p = pipeline.parse("Send invoice asap", reference_now());
+        assert_eq!(p.title, "Send invoice");
+        assert_eq!(local(p.scheduled_for.unwrap()), new_york(2026, 3, 4, 23, 0));
+
+        // Order decides who gets a phrase both extractors recognize
+        let names = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        let p = pipeline.parse_with(
+            "#home Fix sink",
+            reference_now(),
+            &names(&["hash-context", "tags"]),
+        );
+        assert_eq!(p.context, Some("home".to_string()));
+        assert!(p.tags.is_empty());
+
+        let p = pipeline.parse_with(
+            "#home Fix sink",
+            reference_now(),
+            &names(&["tags", "hash-context"]),
+        );
+        assert_eq!(p.context, None);
+        assert_eq!(p.tags, vec!["home"]);
+        assert_eq!(p.title, "Fix sink");
+
+        // Registering a name again replaces the extractor
+        pipeline.register(HashContextExtractor);
+        assert_eq!(pipeline.names().len(), 9);
+    }
+
+    #[test]
+    fn test_status_markers() {
+        let p = parse("Fix the build !now");
+        assert_eq!(p.title, "Fix the build");
+        assert_eq!(p.status, Some(TaskStatus::Now));
+
+        let p = parse("~someday Learn the cello");
+        assert_eq!(p.title, "Learn the cello");
+        assert_eq!(p.status, Some(TaskStatus::Someday));
+
+        let p = parse("Contract draft !waiting @office");
+        assert_eq!(p.title, "Contract draft");
+        assert_eq!(p.status, Some(TaskStatus::Waiting));
+        assert_eq!(p.waiting_on, None);
+
+        // Not a marker inside a word or sentence
+        let p = parse("Say hello!now please");
+        assert_eq!(p.status, None);
+
+        let p = parse("Plain task");
+        assert_eq!(p.status, None);
+    }
+
+    #[test]
+    fn test_waiting_on() {
+        let p = parse("Budget sign-off waiting on Alice Smith tomorrow");
+        assert_eq!(p.title, "Budget sign-off");
+        assert_eq!(p.status, Some(TaskStatus::Waiting));
+        assert_eq!(p.waiting_on, Some("Alice Smith".to_string()));
+        assert!(p.scheduled_for.is_some());
+
+        let p = parse("Parts waiting for the vendor to ship");
+        assert_eq!(p.title, "Parts to ship");
+        assert_eq!(p.waiting_on, Some("the vendor".to_string()));
+
+        // An explicit marker for another status wins, and the phrase stays
+        let p = parse("!now Call Bob waiting on Alice");
+        assert_eq!(p.status, Some(TaskStatus::Now));
+        assert_eq!(p.waiting_on, None);
+        assert_eq!(p.title, "Call Bob waiting on Alice");
+
+        // The marker and the phrase are one token, not two overlapping ones
+        let p = parse("Budget sign-off !waiting on Alice");
+        assert_eq!(p.title, "Budget sign-off");
+        assert_eq!(p.status, Some(TaskStatus::Waiting));
+        assert_eq!(p.waiting_on, Some("Alice".to_string()));
+        assert_eq!(p.tokens.len(), 1);
+        assert_eq!(p.tokens[0].text, "!waiting on Alice");
+    }
+
+    #[test]
+    fn test_priority_markers() {
+        let p = parse("p1 Ship release");
+        assert_eq!(p.title, "Ship release");
+        assert_eq!(p.priority, Some(Priority::High));
+
+        let p = parse("Tidy desk P3");
+        assert_eq!(p.priority, Some(Priority::Low));
+
+        let p = parse("Answer email !!");
+        assert_eq!(p.title, "Answer email");
+        assert_eq!(p.priority, Some(Priority::Medium));
+
+        let p = parse("Fix the build !!! !now");
+        assert_eq!(p.title, "Fix the build");
+        assert_eq!(p.priority, Some(Priority::High));
+        assert_eq!(p.status, Some(TaskStatus::Now));
+
+        // Not markers: part of a word, or a model number
+        let p = parse("Order p4 parts for mp3 player wow!!");
+        assert_eq!(p.priority, None);
+        assert_eq!(p.title, "Order p4 parts for mp3 player wow!!");
+    }
+
+    #[test]
+    fn test_effort_and_energy() {
+        let p = parse("Write summary ~30m #low-energy #work");
+        assert_eq!(p.title, "Write summary");
+        assert_eq!(p.effort_minutes, Some(30));
+        assert_eq!(p.energy, Some(Energy::Low));
+        assert_eq!(p.tags, vec!["work"]);
+
+        let p = parse("Refactor parser ~1h30m #High_Energy");
+        assert_eq!(p.effort_minutes, Some(90));
+        assert_eq!(p.energy, Some(Energy::High));
+
+        let p = parse("Deep clean ~2h");
+        assert_eq!(p.effort_minutes, Some(120));
+
+        // "~someday" is a status, not an estimate
+        let p = parse("Learn Rust ~someday ~0m");
+        assert_eq!(p.effort_minutes, None);
+        assert_eq!(p.status, Some(TaskStatus::Someday));
+        assert_eq!(p.title, "Learn Rust ~0m");
+    }
+}
@@ -0,0 +1,46 @@
+use regex::Regex;
+
+use super::{Capture, Extractor, TokenKind};
+use crate::db::Priority;
+
+/// Priority: "p1" to "p3", or "!!!" (high) and "!!" (medium)
+pub struct PriorityExtractor {
+    marker: Regex,
+}
+
+impl PriorityExtractor {
+    pub fn new() -> Self {
+        PriorityExtractor {
+            marker: Regex::new(r"(?i)(?:^|\s)(p[1-3]|!{2,3})(?:\s|$)").unwrap(),
+        }
+    }
+}
+
+impl Default for PriorityExtractor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Extractor for PriorityExtractor {
+    fn name(&self) -> &str {
+        "priority"
+    }
+
+    /// Only the first marker counts
+    fn extract(&self, capture: &mut Capture) {
+        let Some(caps) = self.marker.captures(capture.text()) else {
+            return;
+        };
+        let marker = caps.get(1).unwrap();
+        let range = marker.range();
+        let priority = match marker.as_str().to_lowercase().as_str() {
+            "p1" | "!!!" => Priority::High,
+            "p2" | "!!" => Priority::Medium,
+            _ => Priority::Low,
+        };
+
+        capture.priority = Some(priority);
+        capture.consume(TokenKind::Priority, range, 1.0);
+    }
+}
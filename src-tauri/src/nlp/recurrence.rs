@@ -0,0 +1,145 @@
+use chrono::{Datelike, Weekday};
+use regex::Regex;
+use std::ops::Range;
+
+use super::{Capture, Extractor, TokenKind};
+use crate::db::Recurrence;
+
+/// Repeat rules: "every Monday", "every 2 weeks", "daily", ...
+pub struct RecurrenceExtractor {
+    after_completion: Regex,
+    every_n: Regex,
+    weekdays: Regex,
+    month_day: Regex,
+    every_weekday: Regex,
+    simple: Regex,
+    day: Regex,
+}
+
+impl RecurrenceExtractor {
+    pub fn new() -> Self {
+        const DAY: &str = r"(?:mon(?:day)?|tue(?:s(?:day)?)?|wed(?:nesday)?|thu(?:rs(?:day)?)?|fri(?:day)?|sat(?:urday)?|sun(?:day)?)s?\b";
+
+        RecurrenceExtractor {
+            after_completion: Regex::new(
+                r"(?i)\bevery (\d+) days? after (?:completion|done|completing)\b",
+            )
+            .unwrap(),
+            every_n: Regex::new(r"(?i)\bevery (\d+|other) (day|week|month)s?\b").unwrap(),
+            weekdays: Regex::new(&format!(
+                r"(?i)\bevery ({0}(?:\s*(?:,|and|&)\s*{0})*)\b",
+                DAY
+            ))
+            .unwrap(),
+            month_day: Regex::new(r"(?i)\bevery month on the (\d{1,2})(?:st|nd|rd|th)?\b").unwrap(),
+            every_weekday: Regex::new(r"(?i)\bevery weekday\b").unwrap(),
+            simple: Regex::new(r"(?i)\b(?:every (day|week|month)|(daily|weekly|monthly))\b")
+                .unwrap(),
+            day: Regex::new(&format!(r"(?i)\b{}", DAY)).unwrap(),
+        }
+    }
+}
+
+impl Default for RecurrenceExtractor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Extractor for RecurrenceExtractor {
+    fn name(&self) -> &str {
+        "recurrence"
+    }
+
+    /// Only the first repeat phrase counts
+    fn extract(&self, capture: &mut Capture) {
+        let today = capture.now.date_naive().day();
+        if let Some((range, recurrence)) = self.find(capture.text(), today) {
+            capture.consume(TokenKind::Recurrence, range, 0.95);
+            capture.recurrence = Some(recurrence);
+        }
+    }
+}
+
+impl RecurrenceExtractor {
+    /// Find a repeat phrase and the rule it describes, if that rule is valid
+    fn find(&self, text: &str, today: u32) -> Option<(Range<usize>, Recurrence)> {
+        let (range, recurrence) = if let Some(caps) = self.after_completion.captures(text) {
+            let days = caps[1].parse().ok()?;
+            (caps.get(0)?.range(), Recurrence::AfterCompletion { days })
+        } else if let Some(caps) = self.month_day.captures(text) {
+            let day = caps[1].parse().ok()?;
+            (
+                caps.get(0)?.range(),
+                Recurrence::Monthly { interval: 1, day },
+            )
+        } else if let Some(caps) = self.every_n.captures(text) {
+            let interval = match &caps[1] {
+                "other" => 2,
+                n => n.parse().ok()?,
+            };
+            let recurrence = match caps[2].to_lowercase().as_str() {
+                "day" => Recurrence::Daily { interval },
+                "week" => Recurrence::Weekly {
+                    interval,
+                    weekdays: Vec::new(),
+                },
+                _ => Recurrence::Monthly {
+                    interval,
+                    day: today,
+                },
+            };
+            (caps.get(0)?.range(), recurrence)
+        } else if let Some(m) = self.every_weekday.find(text) {
+            let weekdays = vec![
+                Weekday::Mon,
+                Weekday::Tue,
+                Weekday::Wed,
+                Weekday::Thu,
+                Weekday::Fri,
+            ];
+            (
+                m.range(),
+                Recurrence::Weekly {
+                    interval: 1,
+                    weekdays,
+                },
+            )
+        } else if let Some(caps) = self.weekdays.captures(text) {
+            let mut weekdays = Vec::new();
+            for day in self.day.find_iter(&caps[1]) {
+                let weekday = day.as_str()[..3].parse::<Weekday>().ok()?;
+                if !weekdays.contains(&weekday) {
+                    weekdays.push(weekday);
+                }
+            }
+            (
+                caps.get(0)?.range(),
+                Recurrence::Weekly {
+                    interval: 1,
+                    weekdays,
+                },
+            )
+        } else if let Some(caps) = self.simple.captures(text) {
+            let unit = caps.get(1).or_else(|| caps.get(2))?.as_str().to_lowercase();
+            let recurrence = match unit.as_str() {
+                "day" | "daily" => Recurrence::Daily { interval: 1 },
+                "week" | "weekly" => Recurrence::Weekly {
+                    interval: 1,
+                    weekdays: Vec::new(),
+                },
+                _ => Recurrence::Monthly {
+                    interval: 1,
+                    day: today,
+                },
+            };
+            (caps.get(0)?.range(), recurrence)
+        } else {
+            return None;
+        };
+
+        // "every 0 days" or "the 45th" stay in the title
+        recurrence.validate().ok()?;
+        Some((range, recurrence))
+    }
+}
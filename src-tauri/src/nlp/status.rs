@@ -0,0 +1,68 @@
+use regex::Regex;
+
+use super::{Capture, Extractor, TokenKind};
+use crate::db::TaskStatus;
+
+/// Initial status: "!now", "!waiting", "~someday", "waiting on Alice", ...
+pub struct StatusExtractor {
+    marker: Regex,
+    waiting_on: Regex,
+}
+
+impl StatusExtractor {
+    pub fn new() -> Self {
+        StatusExtractor {
+            marker: Regex::new(r"(?i)(?:^|\s)(!(?:now|next|waiting|someday)|~someday)\b").unwrap(),
+            // The name is the next word plus any capitalized words after it
+            waiting_on: Regex::new(
+                r"(?i:(?:!|\b)waiting (?:on|for)) ((?i:the )?[\w'.-]+(?: [A-Z][\w'.-]*)*)",
+            )
+            .unwrap(),
+        }
+    }
+}
+
+impl Default for StatusExtractor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Extractor for StatusExtractor {
+    fn name(&self) -> &str {
+        "status"
+    }
+
+    /// The first marker wins; later ones are still removed from the title
+    fn extract(&self, capture: &mut Capture) {
+        if capture.status.is_none() {
+            capture.status = self
+                .marker
+                .captures(capture.text())
+                .and_then(|caps| TaskStatus::from_str(&caps[1][1..].to_lowercase()));
+        }
+
+        // Left in the title when a marker asked for a different status
+        if matches!(capture.status, None | Some(TaskStatus::Waiting)) {
+            if let Some(caps) = self.waiting_on.captures(capture.text()) {
+                let phrase = caps.get(0).unwrap();
+                // Less certain than a marker: "waiting for the bus" is a task, not a status
+                let confidence = if phrase.as_str().starts_with('!') {
+                    1.0
+                } else {
+                    0.8
+                };
+                let (range, name) = (phrase.range(), caps[1].to_string());
+                capture.status = Some(TaskStatus::Waiting);
+                capture.waiting_on = Some(name);
+                capture.consume(TokenKind::Status, range, confidence);
+            }
+        }
+
+        // The remaining markers; "!waiting on Alice" was taken whole above
+        let text = capture.text().to_string();
+        for caps in self.marker.captures_iter(&text) {
+            capture.consume(TokenKind::Status, caps.get(1).unwrap().range(), 1.0);
+        }
+    }
+}
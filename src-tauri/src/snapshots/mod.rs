@@ -0,0 +1,328 @@
+//! Rolling on-disk snapshots of the database.
+//!
+//! A snapshot is a full copy of `taskflow.db` taken with SQLite's online
+//! backup API into `backups/taskflow-YYYYMMDD-HHMMSS.mmm.db` (UTC) and
+//! integrity-checked straight away. The background worker takes one a day;
+//! after each snapshot the newest copy of each of the last N days and the
+//! last M weeks is kept and the rest deleted.
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Datelike, NaiveDateTime, TimeDelta, Utc};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::db::Database;
+
+const NAME_PREFIX: &str = "taskflow-";
+const NAME_SUFFIX: &str = ".db";
+/// Milliseconds are optional when parsing, for names from before they were added
+const NAME_FORMAT: &str = "%Y%m%d-%H%M%S%.3f";
+
+/// Time between scheduled snapshots
+const SNAPSHOT_INTERVAL: i64 = 24 * 60 * 60;
+
+/// How often the background worker wakes up to see if a snapshot is due
+const WORKER_TICK: Duration = Duration::from_secs(60 * 60);
+
+/// A snapshot file in the backups folder
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Snapshot {
+    pub name: String,
+    pub created_at: i64,
+    pub size_bytes: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct SnapshotStore {
+    dir: PathBuf,
+}
+
+impl SnapshotStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Snapshot the database now, then apply the retention settings
+    pub fn create(&self, db: &Database) -> Result<Snapshot> {
+        let snapshot = self.take(db)?;
+
+        let (keep_daily, keep_weekly) = retention(db);
+        self.prune(keep_daily, keep_weekly)?;
+
+        Ok(snapshot)
+    }
+
+    /// Snapshots on disk, newest first. Files that don't look like
+    /// snapshots are ignored.
+    pub fn list(&self) -> Result<Vec<Snapshot>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut snapshots = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if let Some(created_at) = parse_name(&name) {
+                snapshots.push(Snapshot {
+                    name,
+                    created_at,
+                    size_bytes: entry.metadata()?.len(),
+                });
+            }
+        }
+
+        // Names break ties within a second, as they carry the milliseconds
+        snapshots.sort_by(|a, b| (b.created_at, &b.name).cmp(&(a.created_at, &a.name)));
+        Ok(snapshots)
+    }
+
+    /// Replace the database with a snapshot. The current state is
+    /// snapshotted first so the restore can itself be reverted.
+    pub fn restore(&self, db: &mut Database, name: &str) -> Result<Snapshot> {
+        let snapshot = self.get(name)?;
+        // Not pruned, or the snapshot being restored could go with it
+        self.take(db)?;
+        db.restore_from(&self.dir.join(&snapshot.name))?;
+        Ok(snapshot)
+    }
+
+    /// Delete snapshots outside the retention window: the newest snapshot of
+    /// each of the `keep_daily` most recent days and of each of the
+    /// `keep_weekly` most recent weeks survive. Returns how many went.
+    pub fn prune(&self, keep_daily: usize, keep_weekly: usize) -> Result<usize> {
+        let mut days = HashSet::new();
+        let mut weeks = HashSet::new();
+        let mut removed = 0;
+
+        for snapshot in self.list()? {
+            let Some(created) = DateTime::from_timestamp(snapshot.created_at, 0) else {
+                continue;
+            };
+            let day = created.date_naive();
+            let week = created.iso_week();
+
+            let mut keep = false;
+            if !days.contains(&day) && days.len() < keep_daily {
+                days.insert(day);
+                keep = true;
+            }
+            if !weeks.contains(&week) && weeks.len() < keep_weekly {
+                weeks.insert(week);
+                keep = true;
+            }
+
+            if !keep {
+                fs::remove_file(self.dir.join(&snapshot.name))?;
+                removed += 1;
+            }
+        }
+
+        Ok(removed)
+    }
+
+    /// Take a snapshot if the newest one is a day old, or there is none
+    pub fn snapshot_if_due(&self, db: &Database) -> Result<Option<Snapshot>> {
+        let newest = self.list()?.first().map(|snapshot| snapshot.created_at);
+        let due = newest
+            .map(|created_at| Utc::now().timestamp() - created_at >= SNAPSHOT_INTERVAL)
+            .unwrap_or(true);
+
+        if !due {
+            return Ok(None);
+        }
+        self.create(db).map(Some)
+    }
+
+    /// Helper: Snapshot the database without touching older snapshots.
+    /// Never overwrites a file: if the name is taken, the next free
+    /// millisecond is used.
+    fn take(&self, db: &Database) -> Result<Snapshot> {
+        fs::create_dir_all(&self.dir)?;
+
+        let mut at = Utc::now();
+        let name = loop {
+            let name = format!("{}{}{}", NAME_PREFIX, at.format(NAME_FORMAT), NAME_SUFFIX);
+            if !self.dir.join(&name).exists() {
+                break name;
+            }
+            at += TimeDelta::milliseconds(1);
+        };
+        db.snapshot_to(&self.dir.join(&name))?;
+
+        self.get(&name)
+    }
+
+    /// Helper: Look up a snapshot by file name
+    fn get(&self, name: &str) -> Result<Snapshot> {
+        match self.list()?.into_iter().find(|s| s.name == name) {
+            Some(snapshot) => Ok(snapshot),
+            None => bail!("Snapshot {} not found", name),
+        }
+    }
+}
+
+/// Days and weeks of snapshots to keep, from settings
+pub fn retention(db: &Database) -> (usize, usize) {
+    let setting = |key: &str, default: usize| {
+        db.get_setting(key)
+            .ok()
+            .flatten()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default)
+            .max(1)
+    };
+
+    (
+        setting("snapshot_keep_daily", 7),
+        setting("snapshot_keep_weekly", 4),
+    )
+}
+
+/// Creation time encoded in a snapshot file name
+fn parse_name(name: &str) -> Option<i64> {
+    let stamp = name.strip_prefix(NAME_PREFIX)?.strip_suffix(NAME_SUFFIX)?;
+    NaiveDateTime::parse_from_str(stamp, NAME_FORMAT)
+        .ok()
+        .map(|dt| dt.and_utc().timestamp())
+}
+
+/// Start the background worker that takes a snapshot once a day. Failures
+/// are skipped; the next tick tries again.
+pub fn spawn_snapshot_worker(
+    db: Arc<Mutex<Database>>,
+    store: SnapshotStore,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || loop {
+        {
+            let Ok(db) = db.lock() else { break };
+            let _ = store.snapshot_if_due(&db);
+        }
+
+        thread::sleep(WORKER_TICK);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{NewTask, TaskStatus};
+    use tempfile::{tempdir, TempDir};
+
+    fn setup() -> (TempDir, Database, SnapshotStore) {
+        let dir = tempdir().unwrap();
+        let db = Database::new(dir.path().join("taskflow.db")).unwrap();
+        let store = SnapshotStore::new(dir.path().join("backups"));
+        (dir, db, store)
+    }
+
+    fn insert(db: &Database, title: &str) {
+        db.insert_task(&NewTask {
+            title: title.to_string(),
+            status: TaskStatus::Next,
+            source: "test".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+    }
+
+    /// An empty file named like a snapshot taken at `at`
+    fn fake_snapshot(store: &SnapshotStore, at: &str) {
+        fs::create_dir_all(&store.dir).unwrap();
+        fs::write(
+            store
+                .dir
+                .join(format!("{}{}{}", NAME_PREFIX, at, NAME_SUFFIX)),
+            b"",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_snapshot_and_restore() {
+        let (_dir, mut db, store) = setup();
+        insert(&db, "Before");
+
+        let snapshot = store.create(&db).unwrap();
+        assert!(snapshot.size_bytes > 0);
+        assert_eq!(store.list().unwrap(), vec![snapshot.clone()]);
+        assert!(store.snapshot_if_due(&db).unwrap().is_none());
+
+        insert(&db, "After");
+        store.restore(&mut db, &snapshot.name).unwrap();
+
+        let titles: Vec<_> = db
+            .get_all_tasks()
+            .unwrap()
+            .into_iter()
+            .map(|task| task.title)
+            .collect();
+        assert_eq!(titles, vec!["Before"]);
+        assert_eq!(store.list().unwrap().len(), 2);
+
+        // Snapshots taken back to back get names of their own
+        let first = store.create(&db).unwrap();
+        let second = store.create(&db).unwrap();
+        assert_ne!(first.name, second.name);
+        assert_eq!(store.list().unwrap()[0], second);
+
+        assert!(store.restore(&mut db, "../taskflow.db").is_err());
+    }
+
+    #[test]
+    fn test_corrupt_snapshot_rejected() {
+        let (_dir, mut db, store) = setup();
+        insert(&db, "Keep me");
+        fake_snapshot(&store, "20240101-000000");
+        fs::write(
+            store.dir.join("taskflow-20240101-000000.db"),
+            b"definitely not a database",
+        )
+        .unwrap();
+
+        assert!(store
+            .restore(&mut db, "taskflow-20240101-000000.db")
+            .is_err());
+        assert_eq!(db.get_all_tasks().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_prune() {
+        let (_dir, _db, store) = setup();
+        // Mon 1 Jan 2024 twice, Tue 2 Jan, Wed 3 Jan, Mon 8 Jan, Mon 15 Jan.
+        // The last two days are kept, plus the newest of the week before.
+        for at in [
+            "20240101-080000",
+            "20240101-200000",
+            "20240102-080000",
+            "20240103-080000",
+            "20240108-080000",
+            "20240115-080000",
+        ] {
+            fake_snapshot(&store, at);
+        }
+        fs::write(store.dir.join("notes.txt"), b"").unwrap();
+
+        assert_eq!(store.prune(2, 3).unwrap(), 3);
+        let names: Vec<_> = store
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|snapshot| snapshot.name)
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "taskflow-20240115-080000.db",
+                "taskflow-20240108-080000.db",
+                "taskflow-20240103-080000.db",
+            ]
+        );
+        assert!(store.dir.join("notes.txt").exists());
+    }
+}
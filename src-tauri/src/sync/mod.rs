@@ -0,0 +1,886 @@
+//! Git-backed sync engine.
+//!
+//! Every task is serialized to `tasks/<id>.json` and every project to
+//! `projects/<id>.json` inside a local working repository. A sync commits
+//! the local state, fetches the configured remote, merges remote changes into
+//! the database using `sync_version` to tell which side changed since the
+//! last common commit, then pushes the result back.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::db::{Database, Project, Task};
+
+const REMOTE: &str = "origin";
+const BRANCH: &str = "main";
+const TASKS_DIR: &str = "tasks";
+const PROJECTS_DIR: &str = "projects";
+
+/// How often the background worker wakes up to check the sync settings
+const WORKER_TICK: Duration = Duration::from_secs(30);
+
+/// What a single sync run did
+#[derive(Debug, Default, Serialize)]
+pub struct SyncSummary {
+    pub exported: usize,
+    pub imported: usize,
+    pub deleted: usize,
+    pub conflicts: usize,
+    pub pushed: bool,
+}
+
+/// Database access for one step of a sync. A shared database is locked
+/// per step so git's network round trips run without holding it.
+pub trait DbAccess {
+    fn with<R>(&self, f: impl FnOnce(&Database) -> Result<R>) -> Result<R>;
+}
+
+impl DbAccess for Database {
+    fn with<R>(&self, f: impl FnOnce(&Database) -> Result<R>) -> Result<R> {
+        f(self)
+    }
+}
+
+impl DbAccess for Mutex<Database> {
+    fn with<R>(&self, f: impl FnOnce(&Database) -> Result<R>) -> Result<R> {
+        let db = self.lock().map_err(|e| anyhow!("{}", e))?;
+        f(&db)
+    }
+}
+
+/// Check a remote before it reaches git's command line. A leading `-`
+/// would be read as an option, and a line break would split the config.
+pub fn validate_remote(remote: &str) -> Result<()> {
+    if remote.starts_with('-') {
+        bail!("Sync remote cannot start with '-'");
+    }
+    if remote.chars().any(char::is_control) {
+        bail!("Sync remote cannot contain control characters");
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+pub struct SyncEngine {
+    repo_dir: PathBuf,
+    /// Keeps overlapping syncs out of the working repository
+    repo_lock: Arc<Mutex<()>>,
+}
+
+impl SyncEngine {
+    pub fn new(repo_dir: PathBuf) -> Self {
+        SyncEngine {
+            repo_dir,
+            repo_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    pub fn repo_dir(&self) -> &Path {
+        &self.repo_dir
+    }
+
+    /// Run a full sync and record the outcome in `sync_log`. The database
+    /// is only held while exporting and merging, not during fetch and push.
+    pub fn sync(&self, db: &impl DbAccess) -> Result<SyncSummary> {
+        let _repo = self.repo_lock.lock().map_err(|e| anyhow!("{}", e))?;
+        let result = self.run(db);
+
+        db.with(|db| {
+            match &result {
+                Ok(summary) => {
+                    let message = format!(
+                        "exported {}, imported {}, deleted {}, conflicts {}",
+                        summary.exported, summary.imported, summary.deleted, summary.conflicts
+                    );
+                    db.log_sync("sync", "success", Some(&message))?;
+                }
+                Err(e) => {
+                    db.log_sync("sync", "error", Some(&format!("{:#}", e)))?;
+                }
+            }
+            Ok(())
+        })?;
+
+        result
+    }
+
+    fn run(&self, db: &impl DbAccess) -> Result<SyncSummary> {
+        let mut summary = SyncSummary::default();
+
+        // 1. Commit the local state
+        let remote = db.with(|db| {
+            let remote = db.get_setting("git_sync_remote")?.unwrap_or_default();
+            let remote = remote.trim().to_string();
+            self.ensure_repo(&remote)?;
+
+            summary.exported = self.export(db)?;
+            if self.commit("Sync local changes")? {
+                db.log_sync(
+                    "commit",
+                    "success",
+                    Some(&format!("{} records", summary.exported)),
+                )?;
+            }
+            Ok(remote)
+        })?;
+
+        if remote.is_empty() {
+            return Ok(summary);
+        }
+
+        // 2. Pull and merge whatever the remote has that we don't
+        self.git(&["fetch", REMOTE])?;
+
+        let remote_ref = format!("{}/{}", REMOTE, BRANCH);
+        db.with(|db| {
+            db.log_sync("pull", "success", Some(&remote))?;
+            if self.rev_exists(&remote_ref) {
+                let behind =
+                    self.git(&["rev-list", "--count", &format!("HEAD..{}", remote_ref)])?;
+                if behind != "0" {
+                    self.merge(db, &remote_ref, &mut summary)?;
+                }
+            }
+            Ok(())
+        })?;
+
+        // 3. Publish the merged state
+        self.git(&["push", REMOTE, &format!("HEAD:{}", BRANCH)])?;
+        db.with(|db| db.log_sync("push", "success", Some(&remote)))?;
+        summary.pushed = true;
+
+        Ok(summary)
+    }
+
+    /// Create the working repository on first use and point it at the remote
+    fn ensure_repo(&self, remote: &str) -> Result<()> {
+        fs::create_dir_all(&self.repo_dir)
+            .with_context(|| format!("Failed to create {}", self.repo_dir.display()))?;
+
+        if !self.repo_dir.join(".git").exists() {
+            self.git(&["init"])?;
+            self.git(&["symbolic-ref", "HEAD", &format!("refs/heads/{}", BRANCH)])?;
+            self.git(&["commit", "--allow-empty", "-m", "Initialize TaskFlow sync"])?;
+        }
+
+        if !remote.is_empty() {
+            validate_remote(remote)?;
+            match self.git(&["remote", "get-url", REMOTE]) {
+                Ok(url) if url == remote => {}
+                Ok(_) => {
+                    self.git(&["remote", "set-url", REMOTE, remote])?;
+                }
+                Err(_) => {
+                    self.git(&["remote", "add", REMOTE, remote])?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write every project and task to the working tree and drop files for
+    /// records that no longer exist. Returns the number of records written.
+    fn export(&self, db: &Database) -> Result<usize> {
+        Ok(self.export_records::<Project>(db)? + self.export_records::<Task>(db)?)
+    }
+
+    fn export_records<T: SyncRecord>(&self, db: &Database) -> Result<usize> {
+        let dir = self.repo_dir.join(T::DIR);
+        fs::create_dir_all(&dir)?;
+
+        let records = T::load_all(db)?;
+        let mut ids = HashSet::new();
+
+        for record in &records {
+            let json = serde_json::to_string_pretty(record)?;
+            fs::write(dir.join(format!("{}.json", record.id())), json + "\n")?;
+            ids.insert(record.id().to_string());
+        }
+
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            let is_stale = path.extension().is_some_and(|ext| ext == "json")
+                && path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .is_some_and(|id| !ids.contains(id));
+
+            if is_stale {
+                fs::remove_file(&path)?;
+            }
+        }
+
+        Ok(records.len())
+    }
+
+    /// Stage everything and commit. Returns false when there was nothing to commit.
+    fn commit(&self, message: &str) -> Result<bool> {
+        self.git(&["add", "-A"])?;
+
+        if self.git(&["status", "--porcelain"])?.is_empty() {
+            return Ok(false);
+        }
+
+        self.git(&["commit", "-m", message])?;
+        Ok(true)
+    }
+
+    /// Three-way merge of the remote projects and tasks into the database.
+    ///
+    /// A side "changed" a record when its `sync_version` is higher than the one
+    /// in the merge base. If only one side changed it, that side wins; if both
+    /// did, the most recently updated copy wins and the conflict is logged.
+    fn merge(&self, db: &Database, remote_ref: &str, summary: &mut SyncSummary) -> Result<()> {
+        let base = self.git(&["merge-base", "HEAD", remote_ref]).ok();
+        let base_tasks: HashMap<String, Task> = match &base {
+            Some(rev) => self.read_records_at(rev)?,
+            None => HashMap::new(),
+        };
+
+        // Projects first, so the tasks in them have something to point at
+        db.with_event_source("sync", |db| {
+            self.merge_records::<Project>(db, base.as_deref(), &base_tasks, remote_ref, summary)?;
+            self.merge_records::<Task>(db, base.as_deref(), &base_tasks, remote_ref, summary)
+        })?;
+
+        // Record the merge in history, then commit the resolved state on top
+        self.git(&[
+            "merge",
+            "-s",
+            "ours",
+            "--no-edit",
+            "--allow-unrelated-histories",
+            remote_ref,
+        ])?;
+        self.export(db)?;
+        self.commit("Merge remote changes")?;
+
+        let message = format!(
+            "imported {}, deleted {}, conflicts {}",
+            summary.imported, summary.deleted, summary.conflicts
+        );
+        db.log_sync("merge", "success", Some(&message))?;
+
+        Ok(())
+    }
+
+    fn merge_records<T: SyncRecord>(
+        &self,
+        db: &Database,
+        base: Option<&str>,
+        base_tasks: &HashMap<String, Task>,
+        remote_ref: &str,
+        summary: &mut SyncSummary,
+    ) -> Result<()> {
+        let base_records: HashMap<String, T> = match base {
+            Some(rev) => self.read_records_at(rev)?,
+            None => HashMap::new(),
+        };
+        let remote_records: HashMap<String, T> = self.read_records_at(remote_ref)?;
+        let local_records: HashMap<String, T> = T::load_all(db)?
+            .into_iter()
+            .map(|record| (record.id().to_string(), record))
+            .collect();
+
+        for remote in in_dependency_order(&remote_records) {
+            let id = remote.id();
+            let base_version = base_records.get(id).map(|r| r.sync_version()).unwrap_or(0);
+            let remote_changed = remote.sync_version() > base_version;
+
+            match local_records.get(id) {
+                None if base_records.contains_key(id) => {
+                    // Deleted here; only bring it back if the remote edited it since
+                    if remote_changed {
+                        T::upsert(db, remote)?;
+                        summary.imported += 1;
+                        summary.conflicts += 1;
+                        self.log_conflict(
+                            db,
+                            remote,
+                            "deleted locally but edited remotely; restored",
+                        )?;
+                    }
+                }
+                None => {
+                    T::upsert(db, remote)?;
+                    summary.imported += 1;
+                }
+                Some(local) if local == remote => {}
+                Some(local) => {
+                    let local_changed = local.sync_version() > base_version;
+
+                    if remote_changed && !local_changed {
+                        T::upsert(db, remote)?;
+                        summary.imported += 1;
+                    } else if remote_changed && local_changed {
+                        // Bump past both versions so the winner is newer everywhere
+                        let remote_wins = remote.updated_at() > local.updated_at();
+                        let mut winner = if remote_wins {
+                            remote.clone()
+                        } else {
+                            local.clone()
+                        };
+                        winner
+                            .set_sync_version(local.sync_version().max(remote.sync_version()) + 1);
+                        T::upsert(db, &winner)?;
+
+                        if remote_wins {
+                            summary.imported += 1;
+                        }
+                        summary.conflicts += 1;
+                        let resolution = if remote_wins {
+                            "kept remote copy"
+                        } else {
+                            "kept local copy"
+                        };
+                        self.log_conflict(
+                            db,
+                            &winner,
+                            &format!("changed on both sides; {}", resolution),
+                        )?;
+                    }
+                }
+            }
+        }
+
+        for (id, base_record) in &base_records {
+            if remote_records.contains_key(id) {
+                continue;
+            }
+
+            // Deleted remotely; keep it if it was edited here since
+            if let Some(local) = local_records.get(id) {
+                if local.sync_version() > base_record.sync_version() {
+                    summary.conflicts += 1;
+                    self.log_conflict(db, local, "deleted remotely but edited locally; kept")?;
+                } else if T::has_unsynced_dependents(db, id, base_tasks)? {
+                    summary.conflicts += 1;
+                    self.log_conflict(
+                        db,
+                        local,
+                        "deleted remotely but has tasks changed locally; kept",
+                    )?;
+                } else {
+                    T::delete(db, id)?;
+                    summary.deleted += 1;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn log_conflict<T: SyncRecord>(&self, db: &Database, record: &T, detail: &str) -> Result<()> {
+        let message = format!(
+            "{} {} \"{}\" {}",
+            T::KIND,
+            record.id(),
+            record.title(),
+            detail
+        );
+        db.log_sync("merge", "conflict", Some(&message))?;
+        Ok(())
+    }
+
+    /// Read every serialized record of one kind at a given revision
+    fn read_records_at<T: SyncRecord>(&self, rev: &str) -> Result<HashMap<String, T>> {
+        let listing = self.git(&["ls-tree", "--name-only", rev, &format!("{}/", T::DIR)])?;
+        let mut records = HashMap::new();
+
+        for path in listing.lines().filter(|p| p.ends_with(".json")) {
+            let json = self.git(&["show", &format!("{}:{}", rev, path)])?;
+            let record: T = serde_json::from_str(&json)
+                .with_context(|| format!("Invalid {} file {} at {}", T::KIND, path, rev))?;
+            records.insert(record.id().to_string(), record);
+        }
+
+        Ok(records)
+    }
+
+    fn rev_exists(&self, rev: &str) -> bool {
+        self.git(&["rev-parse", "--verify", "--quiet", rev]).is_ok()
+    }
+
+    /// Run a git command in the working repository and return its trimmed stdout
+    fn git(&self, args: &[&str]) -> Result<String> {
+        let output = Command::new("git")
+            .args([
+                "-c",
+                "user.name=TaskFlow",
+                "-c",
+                "user.email=taskflow@localhost",
+            ])
+            .args(args)
+            .current_dir(&self.repo_dir)
+            .output()
+            .context("Failed to run git")?;
+
+        if !output.status.success() {
+            bail!(
+                "git {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+}
+
+/// A kind of record the engine syncs, one `<id>.json` file per record
+trait SyncRecord: Clone + PartialEq + Serialize + DeserializeOwned {
+    /// Directory holding the files in the working tree
+    const DIR: &'static str;
+    /// Name used in conflict messages
+    const KIND: &'static str;
+
+    fn id(&self) -> &str;
+    fn title(&self) -> &str;
+    fn sync_version(&self) -> i32;
+    fn set_sync_version(&mut self, version: i32);
+    fn updated_at(&self) -> i64;
+
+    /// Record of the same kind that must be written before this one
+    fn depends_on(&self) -> Option<&str> {
+        None
+    }
+
+    /// Whether records hanging off this one were added or edited here since
+    /// the merge base, so deleting it would lose local work
+    fn has_unsynced_dependents(
+        _db: &Database,
+        _id: &str,
+        _base_tasks: &HashMap<String, Task>,
+    ) -> Result<bool> {
+        Ok(false)
+    }
+
+    fn load_all(db: &Database) -> Result<Vec<Self>>;
+    fn upsert(db: &Database, record: &Self) -> Result<()>;
+    fn delete(db: &Database, id: &str) -> Result<()>;
+}
+
+impl SyncRecord for Task {
+    const DIR: &'static str = TASKS_DIR;
+    const KIND: &'static str = "Task";
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    fn sync_version(&self) -> i32 {
+        self.sync_version
+    }
+
+    fn set_sync_version(&mut self, version: i32) {
+        self.sync_version = version;
+    }
+
+    fn updated_at(&self) -> i64 {
+        self.updated_at
+    }
+
+    fn depends_on(&self) -> Option<&str> {
+        self.parent_id.as_deref()
+    }
+
+    fn load_all(db: &Database) -> Result<Vec<Self>> {
+        db.get_all_tasks()
+    }
+
+    fn upsert(db: &Database, record: &Self) -> Result<()> {
+        db.upsert_task(record)
+    }
+
+    fn delete(db: &Database, id: &str) -> Result<()> {
+        db.remove_task(id)
+    }
+}
+
+impl SyncRecord for Project {
+    const DIR: &'static str = PROJECTS_DIR;
+    const KIND: &'static str = "Project";
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    fn sync_version(&self) -> i32 {
+        self.sync_version
+    }
+
+    fn set_sync_version(&mut self, version: i32) {
+        self.sync_version = version;
+    }
+
+    fn updated_at(&self) -> i64 {
+        self.updated_at
+    }
+
+    fn load_all(db: &Database) -> Result<Vec<Self>> {
+        db.get_all_projects()
+    }
+
+    fn upsert(db: &Database, record: &Self) -> Result<()> {
+        db.upsert_project(record)
+    }
+
+    fn delete(db: &Database, id: &str) -> Result<()> {
+        db.remove_project(id)
+    }
+
+    fn has_unsynced_dependents(
+        db: &Database,
+        id: &str,
+        base_tasks: &HashMap<String, Task>,
+    ) -> Result<bool> {
+        Ok(db.get_all_tasks()?.iter().any(|task| {
+            task.project_id.as_deref() == Some(id)
+                && base_tasks
+                    .get(&task.id)
+                    .is_none_or(|base| task.sync_version > base.sync_version)
+        }))
+    }
+}
+
+/// Order records so each comes after the one it depends on. Parents
+/// missing from the set (or a corrupt cycle) don't hold anything back.
+fn in_dependency_order<T: SyncRecord>(records: &HashMap<String, T>) -> Vec<&T> {
+    let depth = |record: &T| {
+        let mut depth = 0;
+        let mut current = record;
+        while let Some(parent) = current.depends_on().and_then(|id| records.get(id)) {
+            depth += 1;
+            if depth > records.len() {
+                break;
+            }
+            current = parent;
+        }
+        depth
+    };
+
+    let mut ordered: Vec<&T> = records.values().collect();
+    ordered.sort_by_cached_key(|record| depth(record));
+    ordered
+}
+
+/// Spawn the background worker that syncs every `git_sync_interval_minutes`
+/// while `git_sync_enabled` is "true". Settings are re-read on every tick so
+/// changes take effect without a restart.
+pub fn spawn_background_sync(
+    db: Arc<Mutex<Database>>,
+    engine: SyncEngine,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut last_run: Option<Instant> = None;
+
+        loop {
+            thread::sleep(WORKER_TICK);
+
+            let (enabled, interval_minutes) = {
+                let Ok(db) = db.lock() else { break };
+
+                let enabled =
+                    matches!(db.get_setting("git_sync_enabled"), Ok(Some(v)) if v == "true");
+                let interval_minutes: u64 = db
+                    .get_setting("git_sync_interval_minutes")
+                    .ok()
+                    .flatten()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(5)
+                    .max(1);
+                (enabled, interval_minutes)
+            };
+            if !enabled {
+                continue;
+            }
+
+            let due = last_run
+                .map(|t| t.elapsed() >= Duration::from_secs(interval_minutes * 60))
+                .unwrap_or(true);
+
+            if due {
+                // Failures are recorded in sync_log; try again next interval
+                let _ = engine.sync(&*db);
+                last_run = Some(Instant::now());
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{NewTask, TaskStatus};
+    use tempfile::{tempdir, TempDir};
+
+    struct Device {
+        db: Database,
+        engine: SyncEngine,
+    }
+
+    fn setup_remote() -> (TempDir, String) {
+        let dir = tempdir().unwrap();
+        let remote = dir.path().join("remote.git");
+        let status = Command::new("git")
+            .args(["init", "--bare", "-q"])
+            .arg(&remote)
+            .status()
+            .unwrap();
+        assert!(status.success());
+        (dir, remote.to_string_lossy().to_string())
+    }
+
+    fn setup_device(dir: &TempDir, name: &str, remote: &str) -> Device {
+        let db = Database::new(dir.path().join(format!("{}.db", name))).unwrap();
+        db.set_setting("git_sync_remote", remote).unwrap();
+        let engine = SyncEngine::new(dir.path().join(format!("{}-sync", name)));
+        Device { db, engine }
+    }
+
+    fn create(db: &Database, title: &str) -> Task {
+        db.create_task(
+            title.to_string(),
+            TaskStatus::Next,
+            None,
+            None,
+            None,
+            "test".to_string(),
+            Vec::new(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_sync_without_remote_commits_locally() {
+        let dir = tempdir().unwrap();
+        let device = setup_device(&dir, "a", "");
+        let task = create(&device.db, "Local only");
+
+        let summary = device.engine.sync(&device.db).unwrap();
+
+        assert_eq!(summary.exported, 1);
+        assert!(!summary.pushed);
+        assert!(device
+            .engine
+            .repo_dir()
+            .join(TASKS_DIR)
+            .join(format!("{}.json", task.id))
+            .exists());
+
+        let log = device.db.get_sync_log(10).unwrap();
+        assert_eq!(log[0].operation, "sync");
+        assert_eq!(log[0].status, "success");
+    }
+
+    #[test]
+    fn test_sync_rejects_option_like_remote() {
+        let dir = tempdir().unwrap();
+        let device = setup_device(&dir, "a", "--upload-pack=touch pwned");
+
+        let err = device.engine.sync(&device.db).unwrap_err();
+        assert!(err.to_string().contains("cannot start with '-'"));
+        assert!(device.engine.git(&["remote", "get-url", REMOTE]).is_err());
+
+        assert!(validate_remote("git@example.com:me/tasks.git").is_ok());
+        assert!(validate_remote("/srv/tasks.git\n[core]").is_err());
+    }
+
+    #[test]
+    fn test_sync_round_trip_between_devices() {
+        let (_remote_dir, remote) = setup_remote();
+        let dir = tempdir().unwrap();
+        let a = setup_device(&dir, "a", &remote);
+        let b = setup_device(&dir, "b", &remote);
+
+        let task = create(&a.db, "Shared task");
+        a.engine.sync(&a.db).unwrap();
+
+        let summary = b.engine.sync(&b.db).unwrap();
+        assert_eq!(summary.imported, 1);
+        assert_eq!(b.db.get_task(&task.id).unwrap().title, "Shared task");
+
+        // Edit on B, pull back onto A
+        b.db.update_task_status(&task.id, TaskStatus::Done).unwrap();
+        b.engine.sync(&b.db).unwrap();
+        a.engine.sync(&a.db).unwrap();
+        assert_eq!(a.db.get_task(&task.id).unwrap().status, TaskStatus::Done);
+
+        // Delete on A: the tombstone moves it to the trash on B too
+        a.db.delete_task(&task.id).unwrap();
+        a.engine.sync(&a.db).unwrap();
+        let summary = b.engine.sync(&b.db).unwrap();
+        assert_eq!(summary.imported, 1);
+        assert!(b.db.get_task(&task.id).is_err());
+        assert_eq!(b.db.get_trash().unwrap()[0].id, task.id);
+
+        // Purging it removes it everywhere
+        a.db.purge_task(&task.id).unwrap();
+        a.engine.sync(&a.db).unwrap();
+        let summary = b.engine.sync(&b.db).unwrap();
+        assert_eq!(summary.deleted, 1);
+        assert!(b.db.get_trash().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_sync_conflict_is_logged_and_resolved() {
+        let (_remote_dir, remote) = setup_remote();
+        let dir = tempdir().unwrap();
+        let a = setup_device(&dir, "a", &remote);
+        let b = setup_device(&dir, "b", &remote);
+
+        let task = create(&a.db, "Contested");
+        a.engine.sync(&a.db).unwrap();
+        b.engine.sync(&b.db).unwrap();
+
+        a.db.update_task_status(&task.id, TaskStatus::Waiting)
+            .unwrap();
+        b.db.update_task_status(&task.id, TaskStatus::Someday)
+            .unwrap();
+        a.engine.sync(&a.db).unwrap();
+
+        let summary = b.engine.sync(&b.db).unwrap();
+        assert_eq!(summary.conflicts, 1);
+
+        let log = b.db.get_sync_log(20).unwrap();
+        assert!(log.iter().any(|l| l.status == "conflict"));
+
+        // After A pulls the resolution, both devices agree
+        a.engine.sync(&a.db).unwrap();
+        let on_a = a.db.get_task(&task.id).unwrap();
+        let on_b = b.db.get_task(&task.id).unwrap();
+        assert_eq!(on_a.status, on_b.status);
+        assert_eq!(on_a.sync_version, on_b.sync_version);
+    }
+
+    #[test]
+    fn test_now_task_demotion_syncs() {
+        let (_remote_dir, remote) = setup_remote();
+        let dir = tempdir().unwrap();
+        let a = setup_device(&dir, "a", &remote);
+        let b = setup_device(&dir, "b", &remote);
+
+        let first = create(&a.db, "First");
+        let second = create(&a.db, "Second");
+        a.db.update_task_status(&first.id, TaskStatus::Now).unwrap();
+        a.engine.sync(&a.db).unwrap();
+        b.engine.sync(&b.db).unwrap();
+
+        // B picks another NOW task; A demotes its own when that arrives,
+        // as a change of its own
+        b.db.update_task_status(&second.id, TaskStatus::Now)
+            .unwrap();
+        b.engine.sync(&b.db).unwrap();
+        let before = a.db.get_task(&first.id).unwrap();
+        a.engine.sync(&a.db).unwrap();
+        let demoted = a.db.get_task(&first.id).unwrap();
+        assert_eq!(demoted.status, TaskStatus::Next);
+        assert!(demoted.sync_version > before.sync_version);
+
+        // Both devices end up with the same single NOW task
+        b.engine.sync(&b.db).unwrap();
+        for db in [&a.db, &b.db] {
+            assert_eq!(db.get_task(&first.id).unwrap().status, TaskStatus::Next);
+            assert_eq!(db.get_task(&second.id).unwrap().status, TaskStatus::Now);
+        }
+        assert_eq!(
+            b.db.get_task(&first.id).unwrap().sync_version,
+            demoted.sync_version
+        );
+    }
+
+    #[test]
+    fn test_sync_projects_and_subtasks() {
+        let (_remote_dir, remote) = setup_remote();
+        let dir = tempdir().unwrap();
+        let a = setup_device(&dir, "a", &remote);
+        let b = setup_device(&dir, "b", &remote);
+
+        let project = a.db.create_project("Renovate", None).unwrap();
+        let mut parent_id = None;
+        for title in ["Kitchen", "Cabinets", "Hinges"] {
+            let task =
+                a.db.insert_task(&NewTask {
+                    title: title.to_string(),
+                    source: "test".to_string(),
+                    project_id: Some(project.id.clone()),
+                    parent_id,
+                    ..Default::default()
+                })
+                .unwrap();
+            parent_id = Some(task.id);
+        }
+        a.engine.sync(&a.db).unwrap();
+
+        // Nested subtasks import whatever order the files are read in
+        let summary = b.engine.sync(&b.db).unwrap();
+        assert_eq!(summary.imported, 4);
+        assert_eq!(b.db.get_project_tasks(&project.id).unwrap().len(), 3);
+
+        // Deleting the project trashes its tasks on the other device too
+        b.db.delete_project(&project.id).unwrap();
+        b.engine.sync(&b.db).unwrap();
+        a.engine.sync(&a.db).unwrap();
+        assert!(a.db.get_project(&project.id).is_err());
+        let tasks = a.db.get_all_tasks().unwrap();
+        assert!(!tasks.is_empty());
+        assert!(tasks
+            .iter()
+            .all(|task| task.deleted_at.is_some() && task.project_id.is_none()));
+    }
+
+    #[test]
+    fn test_remote_project_delete_keeps_local_tasks() {
+        let (_remote_dir, remote) = setup_remote();
+        let dir = tempdir().unwrap();
+        let a = setup_device(&dir, "a", &remote);
+        let b = setup_device(&dir, "b", &remote);
+
+        let project = a.db.create_project("Move house", None).unwrap();
+        a.engine.sync(&a.db).unwrap();
+        b.engine.sync(&b.db).unwrap();
+
+        // B deletes the project while A adds a task to it
+        b.db.delete_project(&project.id).unwrap();
+        b.engine.sync(&b.db).unwrap();
+        let task =
+            a.db.insert_task(&NewTask {
+                title: "Book movers".to_string(),
+                source: "test".to_string(),
+                project_id: Some(project.id.clone()),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let summary = a.engine.sync(&a.db).unwrap();
+        assert_eq!(summary.deleted, 0);
+        assert_eq!(summary.conflicts, 1);
+        assert_eq!(
+            a.db.get_task(&task.id).unwrap().project_id,
+            Some(project.id.clone())
+        );
+        assert!(a.db.get_project(&project.id).is_ok());
+
+        // The kept project and its task reach B on the next sync
+        b.engine.sync(&b.db).unwrap();
+        assert!(b.db.get_project(&project.id).is_ok());
+        assert!(b.db.get_task(&task.id).is_ok());
+    }
+}
@@ -1,5 +1,17 @@
 export type TaskStatus = 'now' | 'next' | 'waiting' | 'someday' | 'done';
 
+export type Weekday = 'Mon' | 'Tue' | 'Wed' | 'Thu' | 'Fri' | 'Sat' | 'Sun';
+
+export type Recurrence =
+  | { kind: 'daily'; interval: number }
+  | { kind: 'weekly'; interval: number; weekdays: Weekday[] }
+  | { kind: 'monthly'; interval: number; day: number }
+  | { kind: 'after_completion'; days: number };
+
+export type Priority = 'high' | 'medium' | 'low';
+
+export type Energy = 'low' | 'medium' | 'high';
+
 export interface Task {
   id: string;
   title: string;
@@ -11,13 +23,346 @@ export interface Task {
   updated_at: number;
   original_input?: string;
   source: string;
-  tags?: string;
+  tags: string[];
   sync_version: number;
+  recurrence?: Recurrence | null;
+  waiting_on?: string;
+  waiting_since?: number;
+  follow_up_at?: number;
+  priority?: Priority;
+  effort_minutes?: number;
+  energy?: Energy;
+  project_id?: string;
+  parent_id?: string;
+  /** Set while the task is in the trash */
+  deleted_at?: number;
+}
+
+// Omitted fields are left unchanged; `null` clears a nullable field.
+export interface TaskPatch {
+  title?: string;
+  status?: TaskStatus;
+  context?: string | null;
+  scheduled_for?: number | null;
+  tags?: string[];
+  recurrence?: Recurrence | null;
+  waiting_on?: string | null;
+  follow_up_at?: number | null;
+  priority?: Priority | null;
+  effort_minutes?: number | null;
+  energy?: Energy | null;
+  /** Moves the task and its subtasks; not allowed on a subtask */
+  project_id?: string | null;
+  /** Makes the task a subtask, moving it into the parent's project */
+  parent_id?: string | null;
+}
+
+export interface TaskFilter {
+  statuses?: TaskStatus[];
+  context?: string;
+  tag?: string;
+  scheduled_from?: number;
+  scheduled_to?: number;
+  completed_from?: number;
+  completed_to?: number;
+  text?: string;
+  /** Limit to the current context, if one is set */
+  in_current_context?: boolean;
+}
+
+export type TaskSortField = 'created_at' | 'updated_at' | 'scheduled_for' | 'completed_at' | 'title' | 'priority';
+
+export interface TaskQuery extends TaskFilter {
+  sort?: TaskSortField;
+  direction?: 'asc' | 'desc';
+  cursor?: string;
+  limit?: number;
+}
+
+export interface TaskPage {
+  tasks: Task[];
+  next_cursor: string | null;
+}
+
+// Snippets wrap matched terms in <mark></mark>; other text is not escaped.
+export interface SearchResult {
+  task: Task;
+  title_snippet: string;
+  input_snippet: string | null;
+  rank: number;
+}
+
+export interface TagCount {
+  name: string;
+  task_count: number;
+  open_task_count: number;
+}
+
+export interface ContextCount {
+  name: string;
+  task_count: number;
+  open_task_count: number;
+}
+
+export interface WaitingTask extends Task {
+  follow_up_overdue: boolean;
+  days_waiting: number;
+  last_follow_up_at?: number;
+}
+
+export interface FollowUp {
+  id: string;
+  task_id: string;
+  nudged_at: number;
+  note?: string;
+}
+
+export type ProjectStatus = 'active' | 'someday' | 'done';
+
+export interface Project {
+  id: string;
+  title: string;
+  status: ProjectStatus;
+  notes?: string;
+  created_at: number;
+  updated_at: number;
+  completed_at?: number;
+  sync_version: number;
+}
+
+export interface ProjectPatch {
+  title?: string;
+  status?: ProjectStatus;
+  notes?: string | null;
+}
+
+export interface Progress {
+  total: number;
+  done: number;
+}
+
+export interface ProjectSummary extends Project {
+  progress: Progress;
+  next_action: Task | null;
+  /** Active with nothing NOW or NEXT */
+  needs_next_action: boolean;
 }
 
 export interface DashboardData {
   now_task: Task | null;
   next_tasks: Task[];
-  waiting_tasks: Task[];
+  waiting_tasks: WaitingTask[];
   review_due_in_days: number;
+  current_context: string | null;
+}
+
+export type ReviewDecision = 'keep' | 'complete' | 'defer' | 'delete';
+
+export interface ReviewLog {
+  id: string;
+  started_at: number;
+  completed_at?: number;
+  tasks_triaged: number;
+  tasks_completed: number;
+  tasks_deferred: number;
+  tasks_deleted: number;
+}
+
+/** A task that has had its decision in a review */
+export interface ReviewTriage {
+  review_id: string;
+  task_id: string;
+  triaged_at: number;
+}
+
+export interface SyncLog {
+  id: string;
+  timestamp: number;
+  operation: string;
+  status: string;
+  message?: string;
+}
+
+export interface SyncSummary {
+  exported: number;
+  imported: number;
+  deleted: number;
+  conflicts: number;
+  pushed: boolean;
+}
+
+export type TokenKind = 'context' | 'tag' | 'date' | 'time' | 'recurrence' | 'status' | 'priority' | 'effort' | 'energy';
+
+export interface ParsedToken {
+  kind: TokenKind;
+  /** UTF-8 byte offsets into the input, not UTF-16 string indices */
+  start: number;
+  end: number;
+  text: string;
+  confidence: number;
+}
+
+export interface CapturePreview {
+  title: string;
+  scheduled_for?: number;
+  context?: string;
+  tags: string[];
+  recurrence?: Recurrence;
+  status?: TaskStatus;
+  waiting_on?: string;
+  priority?: Priority;
+  effort_minutes?: number;
+  energy?: Energy;
+  tokens: ParsedToken[];
+}
+
+export interface ExtractorInfo {
+  name: string;
+  enabled: boolean;
+}
+
+export type ScoreFactor = 'schedule' | 'age' | 'context' | 'priority' | 'effort';
+
+export interface ScoreReason {
+  factor: ScoreFactor;
+  points: number;
+  reason: string;
+}
+
+export interface Suggestion {
+  task: Task;
+  score: number;
+  reasons: ScoreReason[];
+}
+
+export type JournalOperation = 'create_task' | 'update_task_status' | 'delete_task';
+
+export interface JournalEntry {
+  id: number;
+  operation: JournalOperation;
+  task_id: string | null;
+  task_title: string | null;
+  created_at: number;
+}
+
+export interface UndoState {
+  undo: JournalEntry | null;
+  redo: JournalEntry | null;
+}
+
+export type TaskEventSource = 'app' | 'sync' | 'undo' | 'redo' | 'review' | 'recurrence' | 'restore';
+
+// `field` is 'created', a Task field name or 'tags'. Values are text:
+// numbers as digits, recurrence as its RRULE, priority as 1-3.
+export interface TaskEvent {
+  id: number;
+  task_id: string;
+  field: string;
+  old_value: string | null;
+  new_value: string | null;
+  created_at: number;
+  source: TaskEventSource;
+}
+
+export interface PeriodCount {
+  /** First day of the period, YYYY-MM-DD */
+  start: string;
+  completed: number;
+}
+
+export interface StatusTime {
+  status: TaskStatus;
+  seconds: number;
+}
+
+export interface CompletionRate {
+  /** null for tasks without a context */
+  name: string | null;
+  created: number;
+  completed: number;
+  rate: number;
+}
+
+export interface Stats {
+  from: number;
+  to: number;
+  completed_per_day: PeriodCount[];
+  /** Weeks start on Monday */
+  completed_per_week: PeriodCount[];
+  median_lead_time_seconds: number | null;
+  time_in_status: StatusTime[];
+  completion_by_context: CompletionRate[];
+  completion_by_source: CompletionRate[];
+}
+
+export interface TodoTxtTask {
+  /** 1-based line number in the imported text */
+  line: number;
+  original: string;
+  title: string;
+  status: TaskStatus;
+  priority: Priority | null;
+  context: string | null;
+  /** The +project word; dashes stand for spaces in project titles */
+  project: string | null;
+  tags: string[];
+  scheduled_for: number | null;
+  created_at: number | null;
+  completed_at: number | null;
+}
+
+export interface SkippedLine {
+  line: number;
+  text: string;
+  reason: string;
+}
+
+export interface ImportReport {
+  dry_run: boolean;
+  tasks: TodoTxtTask[];
+  /** Projects the import creates because no existing one matched */
+  new_projects: string[];
+  skipped: SkippedLine[];
+}
+
+export interface Setting {
+  key: string;
+  value: string;
+  updated_at: number;
+}
+
+export interface Backup {
+  format_version: number;
+  /** Migration version of the database the backup was taken from */
+  schema_version: number;
+  exported_at: number;
+  settings: Setting[];
+  projects: Project[];
+  tasks: Task[];
+  review_log: ReviewLog[];
+  review_triage: ReviewTriage[];
+  sync_log: SyncLog[];
+  follow_ups: FollowUp[];
+  task_events: TaskEvent[];
+}
+
+/** merge: add missing and newer records; replace: wipe and load the backup */
+export type RestoreMode = 'merge' | 'replace';
+
+export interface RestoreSummary {
+  settings: number;
+  projects: number;
+  tasks: number;
+  review_log: number;
+  review_triage: number;
+  sync_log: number;
+  follow_ups: number;
+  task_events: number;
+}
+
+export interface Snapshot {
+  /** File name in the backups folder */
+  name: string;
+  created_at: number;
+  size_bytes: number;
 }